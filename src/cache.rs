@@ -0,0 +1,226 @@
+use octocrab::models::issues::Issue;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// bump this whenever the layout of `PullRequest` changes incompatibly
+const VERSION: u32 = 1;
+
+/// A merged pull request as we got it from the search
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PullRequest {
+    pub org: String,
+    pub repo: String,
+    pub number: u64,
+    pub title: String,
+    pub author: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub merged_at: Option<String>,
+    pub url: String,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+impl PullRequest {
+    pub fn from_issue(issue: &Issue) -> Option<Self> {
+        let url = &issue.html_url;
+        let mut path = url.path().trim_start_matches('/').split('/');
+        let (org, repo) = (path.next()?, path.next()?);
+        Some(PullRequest {
+            org: org.to_owned(),
+            repo: repo.to_owned(),
+            number: u64::try_from(issue.number).ok()?,
+            title: issue.title.clone(),
+            author: issue.user.login.clone(),
+            labels: issue.labels.iter().map(|l| l.name.clone()).collect(),
+            merged_at: issue.closed_at.map(|d| d.to_rfc3339()),
+            url: url.to_string(),
+            body: issue.body.clone(),
+        })
+    }
+
+    /// bors' "Rollup of N pull requests"
+    pub fn is_rollup(&self) -> bool {
+        self.title.starts_with("Rollup of ") && !self.rollup_members().is_empty()
+    }
+
+    /// the `(org, repo, number)` of the PRs that were successfully merged by
+    /// this rollup, as listed in its body
+    pub fn rollup_members(&self) -> Vec<(String, String, u64)> {
+        let mut members = Vec::new();
+        let Some(body) = &self.body else {
+            return members;
+        };
+        let Some((_, merges)) = body.split_once("Successful merges:") else {
+            return members;
+        };
+        // the list ends where the failed merges start
+        let merges = merges.split("Failed merges:").next().unwrap_or("");
+        for line in merges.lines() {
+            let Some(item) = line.trim().strip_prefix("- ") else {
+                continue;
+            };
+            let Some((reference, _title)) = item.split_once(' ') else {
+                continue;
+            };
+            let Some((repo, number)) = reference.split_once('#') else {
+                continue;
+            };
+            let Ok(number) = number.parse() else {
+                continue;
+            };
+            let (org, repo) = match repo.split_once('/') {
+                Some((org, repo)) => (org, repo),
+                None if repo.is_empty() => (&self.org[..], &self.repo[..]),
+                None => continue,
+            };
+            members.push((org.to_owned(), repo.to_owned(), number));
+        }
+        members
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PrCache {
+    version: u32,
+    prs: Vec<PullRequest>,
+}
+
+pub fn write_prs(path: impl AsRef<Path>, prs: &[PullRequest]) -> Result<(), Box<dyn Error>> {
+    let cache = PrCache {
+        version: VERSION,
+        prs: prs.to_vec(),
+    };
+    fs::write(path, serde_json::to_string_pretty(&cache)?)?;
+    Ok(())
+}
+
+pub fn read_prs(path: impl AsRef<Path>) -> Result<Vec<PullRequest>, Box<dyn Error>> {
+    let path = path.as_ref();
+    let cache: PrCache = serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    if cache.version != VERSION {
+        return Err(format!(
+            "{} has cache version {}, expected {}; re-run `twirer prs`",
+            path.display(),
+            cache.version,
+            VERSION
+        )
+        .into());
+    }
+    Ok(cache.prs)
+}
+
+static PROGRESS: &str = "cache/progress.json";
+
+/// How far we got with each search of an unfinished `prs` run
+#[derive(Default, Serialize, Deserialize)]
+pub struct Progress {
+    /// the week spec and searches of the run, so we don't resume another one
+    pub week: String,
+    pub queries: Vec<String>,
+    pub searches: BTreeMap<String, Search>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Search {
+    pub total: u64,
+    /// the number of pages we already have
+    pub pages: u32,
+    pub complete: bool,
+    pub prs: Vec<PullRequest>,
+}
+
+/// the progress of an earlier, interrupted run for the same week and
+/// queries, or a fresh start
+pub fn read_progress(week: &str, queries: &[String]) -> Progress {
+    let progress = fs::read_to_string(PROGRESS)
+        .ok()
+        .and_then(|s| serde_json::from_str::<Progress>(&s).ok());
+    match progress {
+        Some(p) if p.week == week && p.queries == queries => p,
+        stale => {
+            if stale.is_some() {
+                println!("discarding the progress of an earlier run for another week or query");
+            }
+            Progress {
+                week: week.to_owned(),
+                queries: queries.to_vec(),
+                searches: BTreeMap::new(),
+            }
+        }
+    }
+}
+
+pub fn write_progress(progress: &Progress) -> Result<(), Box<dyn Error>> {
+    fs::write(PROGRESS, serde_json::to_string(progress)?)?;
+    Ok(())
+}
+
+pub fn clear_progress() {
+    let _ = fs::remove_file(PROGRESS); // there may be none
+}
+
+/// read the URLs out of a pre-structured `* [title](url)` list
+pub fn read_legacy_urls(path: impl AsRef<Path>) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut urls = Vec::new();
+    for line in BufReader::new(fs::File::open(path)?).lines() {
+        let line = line?;
+        urls.push(match line.rsplit_once("](") {
+            Some((_, r)) => r.trim_end_matches(')').to_owned(),
+            None => line,
+        });
+    }
+    Ok(urls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rollup(body: Option<&str>) -> PullRequest {
+        PullRequest {
+            org: "rust-lang".into(),
+            repo: "rust".into(),
+            number: 100,
+            title: "Rollup of 4 pull requests".into(),
+            author: "bors".into(),
+            labels: vec!["rollup".into()],
+            merged_at: None,
+            url: "https://github.com/rust-lang/rust/pull/100".into(),
+            body: body.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn rollup_members() {
+        let pr = rollup(Some(
+            "Successful merges:\r\n\r\n - #1 (add `Vec::pop_if`)\r\n \
+             - rust-lang/cargo#2 (fix a bug)\r\n - #x (not a number)\r\n \
+             - cargo#3 (no org)\r\n - #4\r\n\r\n\
+             Failed merges:\r\n\r\n - #5 (conflicts)\r\n\r\nr? @ghost",
+        ));
+        assert!(pr.is_rollup());
+        assert_eq!(
+            pr.rollup_members(),
+            [
+                ("rust-lang".into(), "rust".into(), 1),
+                ("rust-lang".into(), "cargo".into(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn not_a_rollup() {
+        assert!(!rollup(None).is_rollup());
+        assert!(!rollup(Some("Just a list:\n - #1 (a PR)")).is_rollup());
+        let mut pr = rollup(Some("Successful merges:\n - #1 (a PR)"));
+        pr.title = "Roll up the docs".into();
+        assert!(!pr.is_rollup());
+    }
+}
@@ -0,0 +1,1186 @@
+use crate::history::History;
+use pulldown_cmark::{BrokenLink, Event, LinkType, Options, Parser, Tag, TagEnd};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+
+mod sections;
+
+/// Something wrong with the draft
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub file: String,
+    /// 1-based
+    pub line: usize,
+    /// 1-based, in characters
+    pub column: usize,
+    pub rule: &'static str,
+    pub message: String,
+    /// how to fix this mechanically, if we know
+    #[serde(skip)]
+    pub fix: Option<Fix>,
+}
+
+/// A replacement within one line of the draft
+#[derive(Debug, Clone)]
+pub struct Fix {
+    /// 0-based
+    pub line: usize,
+    /// in bytes
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+/// A PR as `(org, repo, number)`
+pub type PrKey = (String, String, u64);
+
+/// the PR a GitHub pull request URL points to
+pub fn pr_key(url: &str) -> Option<PrKey> {
+    let rest = url.strip_prefix("https://github.com/")?;
+    let mut parts = rest.splitn(4, '/');
+    let (org, repo) = (parts.next()?, parts.next()?);
+    if parts.next()? != "pull" {
+        return None;
+    }
+    // ignore anchors and sub-pages like `/files`
+    let number = parts.next()?.split(['#', '?', '/']).next()?.parse().ok()?;
+    Some((org.to_owned(), repo.to_owned(), number))
+}
+
+/// What we know about the issue besides the draft
+#[derive(Default)]
+pub struct Context {
+    /// the `[merged]` link definition up to the week spec
+    pub merged: String,
+    /// `cache/week_spec` and the whole `[merged]` link definition for it
+    pub week: Option<(String, String)>,
+    /// the count in `cache/num_prs`
+    pub num_prs: Option<u64>,
+    /// the PRs fetched for this week, if we have them
+    pub prs: Option<HashSet<PrKey>>,
+    /// the PRs of earlier issues
+    pub previous: HashSet<PrKey>,
+    /// what earlier issues included and dropped
+    pub history: History,
+    /// the orgs we search PRs in
+    pub orgs: Vec<String>,
+}
+
+/// A `#`-heading of the draft and the lines up to the next one
+pub struct Chapter<'d> {
+    pub level: usize,
+    pub title: &'d str,
+    /// the (0-based) index of the heading line
+    pub start: usize,
+    pub lines: &'d [&'d str],
+}
+
+/// the `####` subsections of the Rust updates that aren't lists of PRs
+pub const NOT_PR_LISTS: &[&str] = &[
+    "Rust Compiler Performance Triage",
+    "Approved RFCs",
+    "Final Comment Period",
+    "New and Updated RFCs",
+];
+
+/// the text of a heading, without the link if it is one
+pub fn heading_name(title: &str) -> &str {
+    title
+        .strip_prefix('[')
+        .and_then(|t| t.split_once("]("))
+        .map_or(title, |(name, _)| name)
+}
+
+/// split the draft at its headings, the front matter is a chapter of level 0
+pub fn chapters<'d>(lines: &'d [&'d str]) -> Vec<Chapter<'d>> {
+    let heading = |line: &'d str| {
+        let level = line.len() - line.trim_start_matches('#').len();
+        let title = line[level..].strip_prefix(' ')?;
+        (level > 0).then_some((level, title.trim()))
+    };
+    let mut chapters = Vec::new();
+    let (mut level, mut title, mut start) = (0, "", 0);
+    let mut in_fence = false;
+    for (i, &line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some((l, t)) = heading(line) {
+            chapters.push(Chapter {
+                level,
+                title,
+                start,
+                lines: &lines[start..i],
+            });
+            (level, title, start) = (l, t, i);
+        }
+    }
+    chapters.push(Chapter {
+        level,
+        title,
+        start,
+        lines: &lines[start..],
+    });
+    chapters
+}
+
+/// Collects the diagnostics for one file
+pub struct Checker<'d> {
+    file: String,
+    lines: &'d [&'d str],
+    context: &'d Context,
+    /// the line each PR was first listed in
+    listed: HashMap<PrKey, usize>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl<'d> Checker<'d> {
+    pub fn new(file: impl Into<String>, lines: &'d [&'d str], context: &'d Context) -> Self {
+        Checker {
+            file: file.into(),
+            lines,
+            context,
+            listed: HashMap::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// report a problem at the 0-based `line` and byte `offset` into it
+    pub fn error(&mut self, line: usize, offset: usize, rule: &'static str, message: String) {
+        self.push(line, offset, rule, message, None);
+    }
+
+    /// report a problem we can fix by replacing `len` bytes at `offset`
+    fn fixable(
+        &mut self,
+        (line, offset, len): (usize, usize, usize),
+        rule: &'static str,
+        message: String,
+        replacement: impl Into<String>,
+    ) {
+        let fix = Fix {
+            line,
+            range: offset..offset + len,
+            replacement: replacement.into(),
+        };
+        self.push(line, offset, rule, message, Some(fix));
+    }
+
+    fn push(
+        &mut self,
+        line: usize,
+        offset: usize,
+        rule: &'static str,
+        message: String,
+        fix: Option<Fix>,
+    ) {
+        let text = self.lines.get(line).copied().unwrap_or("");
+        let column = text.get(..offset).map_or(1, |t| t.chars().count() + 1);
+        // different passes may find the same problem
+        if self
+            .diagnostics
+            .iter()
+            .any(|d| d.line == line + 1 && d.column == column && d.rule == rule)
+        {
+            return;
+        }
+        self.diagnostics.push(Diagnostic {
+            file: self.file.clone(),
+            line: line + 1,
+            column,
+            rule,
+            message,
+            fix,
+        });
+    }
+
+    /// report a problem at the byte `offset` into the joined lines
+    fn error_at(&mut self, text: &str, offset: usize, rule: &'static str, message: String) {
+        let line = text[..offset].matches('\n').count();
+        let start = text[..offset].rfind('\n').map_or(0, |n| n + 1);
+        self.error(line, offset - start, rule, message);
+    }
+
+    /// check every chapter of the draft
+    pub fn check_draft(&mut self) {
+        self.lint_markdown();
+        let mut in_updates = false;
+        // the names of the enclosing chapters, with their level
+        let mut outer: Vec<(usize, &str)> = Vec::new();
+        let chapters = chapters(self.lines);
+        for (c, chapter) in chapters.iter().enumerate() {
+            let name = heading_name(chapter.title);
+            outer.retain(|&(level, _)| level < chapter.level);
+            let within = |section| outer.iter().any(|&(_, n)| n == section);
+            let (in_fcp, in_events) = (within("Final Comment Period"), within("Upcoming Events"));
+            outer.push((chapter.level, name));
+            // a heading that only groups the ones below
+            let grouping = chapters
+                .get(c + 1)
+                .is_some_and(|next| next.level > chapter.level)
+                && chapter.lines.iter().skip(1).all(|l| l.trim().is_empty());
+            match (chapter.level, name) {
+                (_, "Crate of the Week" | "Quote of the Week") => self.check_markdown(chapter),
+                (_, "Updates from the Rust Project") => {
+                    self.check_markdown(chapter);
+                    self.check_updates(chapter);
+                    in_updates = true;
+                    continue;
+                }
+                (_, "Rust Compiler Performance Triage") => {
+                    self.check_markdown(chapter);
+                    self.check_triage(chapter);
+                }
+                (_, "Approved RFCs" | "New and Updated RFCs") => {
+                    self.check_markdown(chapter);
+                    self.check_link_list(chapter, Some("https://github.com/rust-lang/rfcs/"));
+                }
+                (_, n) if n.starts_with("CFP - ") => {
+                    self.check_markdown(chapter);
+                    self.check_link_list(chapter, None);
+                }
+                // the RFCs, tracking issues and PRs in FCP
+                _ if in_fcp && !grouping => {
+                    self.check_markdown(chapter);
+                    self.check_link_list(chapter, None);
+                }
+                (_, "Upcoming Events") => {
+                    self.check_markdown(chapter);
+                    self.check_events(chapter);
+                }
+                // the regions
+                _ if in_events => {
+                    self.check_markdown(chapter);
+                    self.check_event_region(chapter);
+                }
+                (_, "Jobs") => {
+                    self.check_markdown(chapter);
+                    self.check_jobs(chapter);
+                }
+                (_, n)
+                    if n.starts_with("Call for Participation") || n == "Final Comment Period" =>
+                {
+                    self.check_markdown(chapter);
+                }
+                // the other `####` subsections of the updates list PRs
+                (4, n) if in_updates && !NOT_PR_LISTS.contains(&n) => {
+                    self.check_markdown(chapter);
+                    self.check_pr_list(chapter.start + 1, &chapter.lines[1..]);
+                    continue;
+                }
+                _ => {}
+            }
+            in_updates = false;
+        }
+        self.diagnostics.sort_by_key(|d| (d.line, d.column));
+    }
+
+    /// what a CommonMark parser thinks is wrong with the whole draft
+    fn lint_markdown(&mut self) {
+        let text = self.lines.join("\n");
+        let mut broken = Vec::new();
+        let mut on_broken = |link: BrokenLink<'_>| {
+            // `[text]` alone is most likely meant literally
+            if link.link_type != LinkType::Shortcut {
+                broken.push((link.span, link.reference.into_string()));
+            }
+            None
+        };
+        let parser =
+            Parser::new_with_broken_link_callback(&text, Options::empty(), Some(&mut on_broken));
+        let events = parser.into_offset_iter().collect::<Vec<_>>();
+        for (span, reference) in broken {
+            self.error_at(
+                &text,
+                span.start,
+                "undefined-reference",
+                format!("the link reference `{reference}` is not defined"),
+            );
+        }
+        let mut in_code_block = false;
+        // the marker indentation of the items of each open list
+        let mut lists: Vec<Option<usize>> = Vec::new();
+        for (i, (event, range)) in events.iter().enumerate() {
+            match event {
+                Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+                Event::End(TagEnd::CodeBlock) => in_code_block = false,
+                Event::Text(_) if !in_code_block => self.lint_backticks(&text, range),
+                Event::Start(Tag::Link { dest_url, .. }) => {
+                    if matches!(events.get(i + 1), Some((Event::End(TagEnd::Link), _))) {
+                        self.error_at(
+                            &text,
+                            range.start,
+                            "empty-link-text",
+                            "the link has no text".into(),
+                        );
+                    }
+                    if dest_url.trim().is_empty() {
+                        // point at the target, an empty text is reported at the start
+                        let target = text[range.clone()].rfind("](").map_or(0, |i| i + 1);
+                        self.error_at(
+                            &text,
+                            range.start + target,
+                            "empty-link-target",
+                            "the link has no target".into(),
+                        );
+                    }
+                }
+                Event::Html(html) | Event::InlineHtml(html) if html.contains("<!--") => {
+                    let offset = range.start + text[range.clone()].find("<!--").unwrap_or(0);
+                    self.error_at(&text, offset, "html-comment", "stray HTML comment".into());
+                }
+                Event::Start(Tag::List(_)) => lists.push(None),
+                Event::End(TagEnd::List(_)) => {
+                    lists.pop();
+                }
+                Event::Start(Tag::Item) => {
+                    let line_start = text[..range.start].rfind('\n').map_or(0, |n| n + 1);
+                    let line = &text[line_start..];
+                    let indent = line.len() - line.trim_start_matches(' ').len();
+                    // we can't tell much about items in block quotes and such
+                    if line_start + indent < range.start {
+                        continue;
+                    }
+                    let nested = lists.len() > 1;
+                    match lists.last_mut() {
+                        Some(first @ None) => {
+                            *first = Some(indent);
+                            if !nested && indent > 0 {
+                                self.error_at(
+                                    &text,
+                                    range.start,
+                                    "list-indent",
+                                    "this list is indented, but not nested in an item".into(),
+                                );
+                            }
+                        }
+                        Some(Some(first)) if *first != indent => {
+                            let message = format!(
+                                "list item indentation {indent} differs from the first item's {first}"
+                            );
+                            self.error_at(&text, range.start, "list-indent", message);
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.lint_reference_definitions();
+    }
+
+    /// backticks in text didn't make it into a code span
+    fn lint_backticks(&mut self, text: &str, range: &Range<usize>) {
+        let source = &text[range.clone()];
+        let mut prev = None;
+        for (i, c) in source.char_indices() {
+            // the escaping backslash may be just before the text
+            let before = &text[..range.start + i];
+            let escaped = (before.len() - before.trim_end_matches('\\').len()) % 2 == 1;
+            if c == '`' && prev != Some('`') && !escaped {
+                self.error_at(
+                    text,
+                    range.start + i,
+                    "code-span",
+                    "unmatched backtick".into(),
+                );
+            }
+            prev = Some(c);
+        }
+    }
+
+    /// reference definitions with a label defined before
+    fn lint_reference_definitions(&mut self) {
+        let mut labels = HashMap::new();
+        let mut in_fence = false;
+        for (i, line) in self.lines.iter().enumerate() {
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+            }
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            let label = line[indent..]
+                .strip_prefix('[')
+                .and_then(|l| l.split_once("]:"))
+                .map(|(label, _)| label);
+            let Some(label) = label.filter(|_| !in_fence && indent < 4) else {
+                continue;
+            };
+            // labels match case-insensitively, ignoring whitespace differences
+            let normalized = label
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase();
+            if let Some(first) = labels.insert(normalized, i) {
+                self.error(
+                    i,
+                    indent,
+                    "duplicate-reference",
+                    format!("`[{label}]` was already defined in line {}", first + 1),
+                );
+            }
+        }
+    }
+
+    /// the PR count and `[merged]` link, then the PRs
+    fn check_updates(&mut self, chapter: &Chapter<'_>) {
+        let merged = &self.context.merged;
+        let mut lines = (chapter.start..)
+            .zip(chapter.lines)
+            .skip(1)
+            .filter(|(_, l)| !l.trim().is_empty());
+        const COUNT: &str = " pull requests were [merged in the last week][merged]";
+        match lines.next() {
+            Some((i, l)) if l.ends_with(COUNT) => self.check_count(i, &l[..l.len() - COUNT.len()]),
+            Some((i, _)) => self.error(
+                i,
+                0,
+                "updates-count",
+                "expected `N pull requests were [merged in the last week][merged]`".into(),
+            ),
+            None => self.error(
+                chapter.start,
+                0,
+                "updates-count",
+                "the PR count is missing".into(),
+            ),
+        }
+        match lines.next() {
+            Some((i, l)) if l.starts_with(merged) => {
+                let week = self.context.week.as_ref();
+                if let Some((spec, _)) = week.filter(|(_, link)| l.trim_end() != link) {
+                    self.error(
+                        i,
+                        merged.len(),
+                        "merged-range",
+                        format!("the `[merged]` link doesn't search `merged:{spec}` as in `cache/week_spec`"),
+                    );
+                }
+            }
+            Some((i, _)) => self.error(
+                i,
+                0,
+                "merged-link",
+                format!("expected the `[merged]` link, starting with `{merged}`"),
+            ),
+            None => self.error(
+                chapter.start,
+                0,
+                "merged-link",
+                "the `[merged]` link is missing".into(),
+            ),
+        }
+        if let Some((i, _)) = lines.next() {
+            let draft = self.lines;
+            self.check_pr_list(i, &draft[i..chapter.start + chapter.lines.len()]);
+        }
+    }
+
+    /// the PR count against the one `twirer prs` came up with and the PRs it got
+    fn check_count(&mut self, line: usize, count: &str) {
+        let Ok(count) = count.parse::<u64>() else {
+            self.error(
+                line,
+                0,
+                "updates-count",
+                format!("`{count}` is not a number"),
+            );
+            return;
+        };
+        let context = self.context;
+        if let Some(num_prs) = context.num_prs.filter(|&n| n != count) {
+            self.error(
+                line,
+                0,
+                "updates-count",
+                format!("the draft says {count} PRs, but `cache/num_prs` says {num_prs}"),
+            );
+        } else if let Some(prs) = context.prs.as_ref().filter(|prs| prs.len() as u64 != count) {
+            self.error(
+                line,
+                0,
+                "updates-count",
+                format!(
+                    "the draft says {count} PRs, but we fetched {}; re-run `twirer prs`",
+                    prs.len()
+                ),
+            );
+        }
+    }
+
+    /// `* [title](link)` lines, starting at line `start`
+    fn check_pr_list(&mut self, start: usize, lines: &[&str]) {
+        for (i, pr) in (start..).zip(lines) {
+            // trailing whitespace is reported (and fixed) on its own
+            let pr = pr.trim_end();
+            if pr.is_empty() {
+                continue;
+            }
+            let entry = pr
+                .strip_prefix("* [")
+                .and_then(|p| p.strip_suffix(')'))
+                .and_then(|p| p.split_once("]("));
+            match entry {
+                Some((title, link)) => {
+                    self.check_title(i, 3, title);
+                    self.check_link(i, pr.len() - 1 - link.len(), link);
+                }
+                None => self.error(
+                    i,
+                    0,
+                    "pr-entry",
+                    "expected a PR as `* [title](link)`".into(),
+                ),
+            }
+        }
+    }
+
+    fn check_link(&mut self, line: usize, offset: usize, link: &str) {
+        if let Some(rest) = link.strip_prefix("http://github.com/") {
+            self.fixable(
+                (line, offset, "http".len()),
+                "pr-link-http",
+                "GitHub links should use https".into(),
+                "https",
+            );
+            return self.check_link(line, offset, &format!("https://github.com/{rest}"));
+        }
+        let Some(rest) = link.strip_prefix("https://github.com/") else {
+            self.error(line, offset, "pr-link", "not a GitHub link".into());
+            return;
+        };
+        let mut parts = rest.splitn(4, '/');
+        let org = parts.next().unwrap_or("?");
+        let repo = parts.next().unwrap_or("?");
+        let kind = parts.next().unwrap_or("?");
+        if kind == "issues" {
+            self.error(
+                line,
+                offset,
+                "pr-issue-link",
+                "this links an issue, not a PR".into(),
+            );
+            return;
+        }
+        let valid_repo = repo
+            .chars()
+            .all(|c| ['_', '-', '.'].contains(&c) || c.is_ascii_alphanumeric());
+        let key = pr_key(&format!("https://github.com/{rest}"));
+        let Some(key) = key.filter(|_| valid_repo) else {
+            self.error(line, offset, "pr-link", format!("wrong link: {link}"));
+            return;
+        };
+        let context = self.context;
+        if !context.orgs.is_empty() && !context.orgs.iter().any(|o| o == org) {
+            self.error(
+                line,
+                offset,
+                "pr-org",
+                format!("`{org}` is not one of the orgs we search"),
+            );
+        }
+        if let Some(&first) = self.listed.get(&key) {
+            self.error(
+                line,
+                offset,
+                "pr-duplicate",
+                format!("this PR is already listed in line {}", first + 1),
+            );
+            return;
+        }
+        self.listed.insert(key.clone(), line);
+        if context.previous.contains(&key) {
+            self.error(
+                line,
+                offset,
+                "pr-previous",
+                "this PR was already in an earlier issue, see `twirer history find`".into(),
+            );
+        } else if let Some((issue, reason)) = context.history.dropped(link) {
+            self.error(
+                line,
+                offset,
+                "pr-dropped",
+                format!("this PR was dropped from issue {} ({reason})", issue.number),
+            );
+        } else if context.prs.as_ref().is_some_and(|prs| !prs.contains(&key)) {
+            self.error(
+                line,
+                offset,
+                "pr-not-merged",
+                "this PR is not among the ones merged this week, try `twirer prs`".into(),
+            );
+        }
+    }
+
+    fn check_title(&mut self, line: usize, offset: usize, title: &str) {
+        // the start and length of the fence of the code span we're in
+        let mut in_code: Option<(usize, usize)> = None;
+        let mut escape = false;
+        let mut chars = title.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c == '`' && !escape {
+                let mut fence = 1;
+                while chars.next_if(|&(_, c)| c == '`').is_some() {
+                    fence += 1;
+                }
+                in_code = match in_code {
+                    None => Some((i, fence)),
+                    Some((_, open)) if open == fence => None,
+                    code => code,
+                };
+            } else if in_code.is_none() {
+                if !escape && ['<', '>', '[', ']', '_'].contains(&c) {
+                    self.fixable(
+                        (line, offset + i, 0),
+                        "title-unescaped",
+                        format!("unescaped `{c}` in non-code title"),
+                        "\\",
+                    );
+                }
+                escape = !escape && c == '\\';
+            }
+        }
+        if escape {
+            self.fixable(
+                (line, offset + title.len() - 1, 1),
+                "title-backslash",
+                "wonky backslash at the end of the title".into(),
+                "",
+            );
+        } else if let Some((i, _)) = in_code {
+            self.error(
+                line,
+                offset + i,
+                "title-backticks",
+                "unmatched backtick in title".into(),
+            );
+        }
+    }
+
+    fn check_markdown(&mut self, chapter: &Chapter<'_>) {
+        for (i, l) in (chapter.start..).zip(chapter.lines) {
+            // no trailing whitespace, or two spaces for a line break
+            let trimmed = l.trim_end();
+            if !matches!(l.len() - trimmed.len(), 0 | 2) {
+                self.fixable(
+                    (i, trimmed.len(), l.len() - trimmed.len()),
+                    "trailing-whitespace",
+                    "line ends with irregular whitespace".into(),
+                    "",
+                );
+            }
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "error[{}]: {}\n --> {}:{}:{}",
+            self.rule, self.message, self.file, self.line, self.column
+        )
+    }
+}
+
+/// the lines with all fixes applied that don't overlap an earlier one
+pub fn apply_fixes(lines: &[&str], diagnostics: &[Diagnostic]) -> Vec<String> {
+    let mut fixes = diagnostics
+        .iter()
+        .filter_map(|d| d.fix.as_ref())
+        .collect::<Vec<_>>();
+    // back to front, so the offsets of the remaining fixes stay valid
+    fixes.sort_by_key(|f| std::cmp::Reverse((f.line, f.range.start)));
+    let mut fixed = lines.iter().map(|&l| l.to_owned()).collect::<Vec<_>>();
+    let mut last: Option<&Fix> = None;
+    for fix in fixes {
+        if last.is_some_and(|l| l.line == fix.line && l.range.start < fix.range.end) {
+            continue;
+        }
+        fixed[fix.line].replace_range(fix.range.clone(), &fix.replacement);
+        last = Some(fix);
+    }
+    fixed
+}
+
+/// a unified diff between two versions of the same lines, as fixes never
+/// add or remove lines
+pub fn diff(file: &str, old: &[&str], new: &[String]) -> String {
+    const CONTEXT: usize = 3;
+    let changed = (0..old.len())
+        .filter(|&i| old[i] != new[i])
+        .collect::<Vec<_>>();
+    let mut out = String::new();
+    if changed.is_empty() {
+        return out;
+    }
+    out.push_str(&format!("--- a/{file}\n+++ b/{file}\n"));
+    let mut i = 0;
+    while i < changed.len() {
+        // changes with overlapping context go into one hunk
+        let mut j = i;
+        while j + 1 < changed.len() && changed[j + 1] - changed[j] <= 2 * CONTEXT {
+            j += 1;
+        }
+        let start = changed[i].saturating_sub(CONTEXT);
+        let end = (changed[j] + CONTEXT + 1).min(old.len());
+        let len = end - start;
+        out.push_str(&format!(
+            "@@ -{},{len} +{},{len} @@\n",
+            start + 1,
+            start + 1
+        ));
+        let (mut removed, mut added) = (String::new(), String::new());
+        for k in start..end {
+            if old[k] == new[k] {
+                out.push_str(&removed);
+                out.push_str(&added);
+                (removed, added) = (String::new(), String::new());
+                out.push_str(&format!(" {}\n", old[k]));
+            } else {
+                removed.push_str(&format!("-{}\n", old[k]));
+                added.push_str(&format!("+{}\n", new[k]));
+            }
+        }
+        out.push_str(&removed);
+        out.push_str(&added);
+        i = j + 1;
+    }
+    out
+}
+
+/// print the diagnostics like rustc does, with the offending line
+pub fn print(diagnostics: &[Diagnostic], lines: &[&str]) {
+    for d in diagnostics {
+        let number = d.line.to_string();
+        let pad = " ".repeat(number.len());
+        println!("{d}");
+        if let Some(line) = lines.get(d.line - 1) {
+            println!("{pad} |\n{number} | {line}");
+            println!("{pad} | {}^\n", " ".repeat(d.column - 1));
+        }
+    }
+    match diagnostics.len() {
+        0 => {}
+        1 => println!("error: there was 1 error"),
+        n => println!("error: there were {n} errors"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use twirer::title::{format_title, TitleOptions};
+
+    /// bits of titles that are hard to get right
+    static FRAGMENTS: &[&str] = &[
+        "Fix",
+        "`a`",
+        "`",
+        "``",
+        "\\",
+        "\\\\",
+        "_",
+        "foo_bar",
+        "<T>",
+        "Vec<T>",
+        "[x]",
+        "[`Option`]",
+        "*",
+        "|",
+        "#",
+        "docs.rs:",
+        "->",
+        "``a`b``",
+        "(",
+        ")",
+        "it!",
+        "HashMap",
+        "a/b/c.rs",
+        "'",
+        "\"",
+    ];
+
+    fn check(lines: &[&str]) -> Vec<Diagnostic> {
+        let context = Context::default();
+        let mut checker = Checker::new("draft.md", lines, &context);
+        checker.check_draft();
+        checker.diagnostics
+    }
+
+    #[test]
+    fn fixes_converge() {
+        let draft = [
+            "Title: This Week in Rust 600",
+            "",
+            "## Updates from the Rust Project",
+            "",
+            "2 pull requests were [merged in the last week][merged]",
+            "",
+            "[merged]: https://github.com/search?q=is%3Apr",
+            "",
+            "#### Compiler",
+            "",
+            "* [fix foo_bar for <T>](http://github.com/rust-lang/rust/pull/1)  \t",
+            "* [keep this](https://github.com/rust-lang/rust/pull/2)",
+        ];
+        let diagnostics = check(&draft);
+        let rules = diagnostics.iter().map(|d| d.rule).collect::<Vec<_>>();
+        assert_eq!(
+            rules,
+            [
+                "title-unescaped",
+                "title-unescaped",
+                "title-unescaped",
+                "pr-link-http",
+                "trailing-whitespace"
+            ]
+        );
+        let fixed = apply_fixes(&draft, &diagnostics);
+        assert_eq!(
+            fixed[10],
+            "* [fix foo\\_bar for \\<T\\>](https://github.com/rust-lang/rust/pull/1)"
+        );
+        let fixed = fixed.iter().map(|l| &l[..]).collect::<Vec<_>>();
+        let left = check(&fixed);
+        assert!(left.is_empty(), "{left:?}");
+    }
+
+    /// the updates as they are laid out in recent issues
+    static UPDATES: &[&str] = &[
+        "Title: This Week in Rust 600",
+        "",
+        "## Updates from the Rust Project",
+        "",
+        "2 pull requests were [merged in the last week][merged]",
+        "",
+        "[merged]: https://github.com/search?q=is%3Apr",
+        "",
+        "#### Compiler",
+        "",
+        "* [stabilize `let_chains`](https://github.com/rust-lang/rust/pull/1)",
+        "",
+        "#### Library",
+        "",
+        "* [add `Vec::pop_if`](https://github.com/rust-lang/rust/pull/2)",
+        "",
+        "#### Rust Compiler Performance Triage",
+        "",
+        "A quiet week.",
+        "",
+        "Triage done by **@someone**.",
+        "Revision range: [a..b](https://perf.rust-lang.org/?start=a&end=b&absolute=false&stat=instructions%3Au)",
+        "",
+        "Full report [here](https://github.com/rust-lang/rustc-perf/blob/master/triage/2026/2026-10-20.md)",
+        "",
+        "#### [Approved RFCs](https://github.com/rust-lang/rfcs/commits/master)",
+        "",
+        "* [RFC: Some feature](https://github.com/rust-lang/rfcs/pull/3000)",
+        "",
+        "#### Final Comment Period",
+        "",
+        "Every week, the team announces the 'final comment period' for RFCs and key PRs.",
+        "",
+        "##### [RFCs](https://github.com/rust-lang/rfcs/labels/final-comment-period)",
+        "",
+        "* *No RFCs entered Final Comment Period this week.*",
+        "",
+        "##### [Tracking Issues & PRs](https://github.com/rust-lang/rust/issues?q=label%3Afinal-comment-period)",
+        "",
+        "* [Stabilize `foo`](https://github.com/rust-lang/rust/pull/3)",
+        "",
+        "#### New and Updated RFCs",
+        "",
+        "* *No New or Updated RFCs were created this week.*",
+    ];
+
+    #[test]
+    fn updates_subsections() {
+        let left = check(UPDATES);
+        assert!(left.is_empty(), "{left:?}");
+        // a draft that starts right with a heading
+        assert!(check(&UPDATES[2..]).is_empty());
+        // the section validators do run
+        let mut draft = UPDATES.to_vec();
+        draft[23] = "Full report [here](https://github.com/rust-lang/rustc-perf/blob/master/triage/2025/2026-10-20.md)";
+        draft[27] = "* [RFC: Some feature](https://github.com/rust-lang/rust/pull/3000)";
+        let rules = check(&draft).iter().map(|d| d.rule).collect::<Vec<_>>();
+        assert_eq!(rules, ["triage-report", "section-link"]);
+    }
+
+    #[test]
+    fn previous_and_dropped() {
+        use crate::history::{Dropped, Issue};
+        let mut context = Context::default();
+        context.history.record(Issue {
+            number: 599,
+            week: None,
+            included: vec!["https://github.com/rust-lang/rust/pull/1".into()],
+            dropped: vec![Dropped {
+                url: "https://github.com/rust-lang/rust/pull/2".into(),
+                reason: "rule 1".into(),
+            }],
+        });
+        context.previous = context
+            .history
+            .urls()
+            .iter()
+            .filter_map(|u| pr_key(u))
+            .collect();
+        let lines = UPDATES[..16].to_vec();
+        let mut checker = Checker::new("draft.md", &lines, &context);
+        checker.check_draft();
+        let found = checker
+            .diagnostics
+            .iter()
+            .map(|d| (d.rule, &d.message[..]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (
+                    "pr-previous",
+                    "this PR was already in an earlier issue, see `twirer history find`"
+                ),
+                ("pr-dropped", "this PR was dropped from issue 599 (rule 1)"),
+            ]
+        );
+    }
+
+    /// every formatted title passes `check_title`, however odd the input
+    #[test]
+    fn formatted_titles_pass_check() {
+        let context = Context::default();
+        let options = TitleOptions::default();
+        let mut checker = Checker::new("draft.md", &[], &context);
+        for a in FRAGMENTS {
+            for b in FRAGMENTS {
+                for c in FRAGMENTS {
+                    for sep in [" ", ""] {
+                        let title = format_title(&[*a, b, c].join(sep), &options);
+                        assert!(!title.contains("  "), "double space in {title:?}");
+                        assert_eq!(title.trim(), title, "{title:?}");
+                        checker.check_title(0, 0, &title);
+                        if let Some(d) = checker.diagnostics.first() {
+                            panic!(
+                                "{}: {} in {title:?} from {:?}",
+                                d.rule,
+                                d.message,
+                                [*a, b, c].join(sep)
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pr_keys() {
+        let key = |org: &str, repo: &str, n| Some((org.to_owned(), repo.to_owned(), n));
+        assert_eq!(
+            pr_key("https://github.com/rust-lang/rust/pull/1"),
+            key("rust-lang", "rust", 1)
+        );
+        assert_eq!(
+            pr_key("https://github.com/rust-lang/cargo/pull/2/files"),
+            key("rust-lang", "cargo", 2)
+        );
+        assert_eq!(
+            pr_key("https://github.com/rust-lang/rust/pull/3#issuecomment-4"),
+            key("rust-lang", "rust", 3)
+        );
+        assert_eq!(pr_key("https://github.com/rust-lang/rust/issues/1"), None);
+        assert_eq!(pr_key("http://github.com/rust-lang/rust/pull/1"), None);
+        assert_eq!(pr_key("https://github.com/rust-lang/rust/pull/x"), None);
+    }
+
+    fn fix(line: usize, range: Range<usize>, replacement: &str) -> Diagnostic {
+        Diagnostic {
+            file: "draft.md".into(),
+            line: line + 1,
+            column: range.start + 1,
+            rule: "test",
+            message: String::new(),
+            fix: Some(Fix {
+                line,
+                range,
+                replacement: replacement.into(),
+            }),
+        }
+    }
+
+    #[test]
+    fn overlapping_fixes() {
+        let lines = ["abcdef", "ghi"];
+        let diagnostics = [
+            fix(0, 0..1, "A"),
+            // overlaps the one after, so it waits for the next run
+            fix(0, 2..4, "CD"),
+            fix(0, 3..5, "xx"),
+            fix(1, 0..3, "GHI"),
+        ];
+        assert_eq!(apply_fixes(&lines, &diagnostics), ["Abcxxf", "GHI"]);
+    }
+
+    #[test]
+    fn diff_hunks() {
+        let old = (1..=12).map(|i| format!("line {i}")).collect::<Vec<_>>();
+        let old = old.iter().map(|l| &l[..]).collect::<Vec<_>>();
+        assert_eq!(
+            diff(
+                "draft.md",
+                &old,
+                &old.iter().map(|&l| l.into()).collect::<Vec<_>>()
+            ),
+            ""
+        );
+        let mut new = old.iter().map(|&l| l.to_owned()).collect::<Vec<_>>();
+        new[1] = "line two".into();
+        new[2] = "line three".into();
+        new[11] = "line twelve".into();
+        assert_eq!(
+            diff("draft.md", &old, &new),
+            "--- a/draft.md\n+++ b/draft.md\n\
+             @@ -1,6 +1,6 @@\n line 1\n-line 2\n-line 3\n+line two\n+line three\n line 4\n line 5\n line 6\n\
+             @@ -9,4 +9,4 @@\n line 9\n line 10\n line 11\n-line 12\n+line twelve\n"
+        );
+    }
+
+    /// the sections after the updates
+    static SECTIONS: &[&str] = &[
+        "## Call for Participation; projects and speakers",
+        "",
+        "### CFP - Projects",
+        "",
+        "* *No Calls for participation were submitted this week.*",
+        "",
+        "## Upcoming Events",
+        "",
+        "Rusty Events between 2026-10-22 - 2026-11-19 🦀",
+        "",
+        "### Virtual",
+        "",
+        "* 2026-10-23 | Virtual | [Rust Meetup](https://example.com/meetup)",
+        "    * [**Monthly Talk**](https://example.com/meetup/1)",
+        "",
+        "## Jobs",
+        "",
+        "Please see the latest [Who's Hiring thread on r/rust](https://www.reddit.com/r/rust/comments/1)",
+    ];
+
+    #[test]
+    fn other_sections() {
+        let left = check(SECTIONS);
+        assert!(left.is_empty(), "{left:?}");
+        let mut draft = SECTIONS.to_vec();
+        draft[4] = "";
+        draft[8] = "Rusty Events between 2026-10-22 and soon";
+        draft[12] = "* 2026-10-32 | Virtual | [Rust Meetup](https://example.com/meetup)";
+        draft[13] = "    * Monthly Talk";
+        draft[17] = "Please see the latest Who's Hiring thread on r/rust";
+        let found = check(&draft)
+            .iter()
+            .map(|d| (d.line, d.rule))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (3, "empty-section"),
+                (9, "events-dates"),
+                (13, "event-date"),
+                (14, "event-entry"),
+                (16, "jobs-link"),
+            ]
+        );
+    }
+
+    /// the line and rule of each diagnostic
+    fn found(lines: &[&str]) -> Vec<(usize, &'static str)> {
+        check(lines).iter().map(|d| (d.line, d.rule)).collect()
+    }
+
+    #[test]
+    fn references() {
+        assert!(found(&["see [the docs][docs]", "", "[docs]: https://docs.rs"]).is_empty());
+        assert_eq!(
+            found(&["see [the docs][docs]"]),
+            [(1, "undefined-reference")]
+        );
+        // a lone `[text]` is meant literally
+        assert!(found(&["the [unstable] book"]).is_empty());
+        assert!(found(&["[a]: https://a.org", "[b]: https://b.org"]).is_empty());
+        assert_eq!(
+            found(&["[a]: https://a.org", "[A]: https://b.org"]),
+            [(2, "duplicate-reference")]
+        );
+        // fenced code isn't markdown
+        assert!(found(&["[a]: https://a.org", "```", "[a]: x", "```"]).is_empty());
+    }
+
+    #[test]
+    fn links() {
+        assert!(found(&["a [link](https://a.org)"]).is_empty());
+        assert_eq!(found(&["a [](https://a.org)"]), [(1, "empty-link-text")]);
+        assert_eq!(found(&["a [link]()"]), [(1, "empty-link-target")]);
+        let diagnostics = check(&["a [link]()"]);
+        // pointing at the missing target
+        assert_eq!(diagnostics[0].column, 9);
+    }
+
+    #[test]
+    fn html_comments() {
+        assert!(found(&["no <b>comments</b> here"]).is_empty());
+        assert_eq!(found(&["<!-- COTW goes here -->"]), [(1, "html-comment")]);
+        assert_eq!(found(&["a <!-- stray --> comment"]), [(1, "html-comment")]);
+    }
+
+    #[test]
+    fn list_indent() {
+        assert!(found(&["* a", "* b", "    * nested", "    * nested"]).is_empty());
+        assert_eq!(found(&[" * a", " * b"]), [(1, "list-indent")]);
+        assert_eq!(
+            found(&["* a", "    * nested", "     * nested"]),
+            [(3, "list-indent")]
+        );
+    }
+
+    #[test]
+    fn code_spans() {
+        assert!(found(&["a `code` span", "``a`b`` too"]).is_empty());
+        assert_eq!(found(&["a `missing backtick"]), [(1, "code-span")]);
+        // an escaped backtick is meant literally
+        assert!(found(&["an escaped \\` backtick"]).is_empty());
+        assert_eq!(found(&["not escaped \\\\` here"]), [(1, "code-span")]);
+        // code blocks may have any backticks
+        assert!(found(&["```", "a ` b", "```"]).is_empty());
+    }
+
+    #[test]
+    fn problems_in_one_place() {
+        // a triage with neither the revision range nor the report
+        assert_eq!(
+            found(&UPDATES[..19]),
+            [(17, "triage-range"), (17, "triage-report")]
+        );
+        let context = Context {
+            orgs: vec!["rust-lang".into()],
+            prs: Some(HashSet::new()),
+            ..Context::default()
+        };
+        let lines = ["* [a PR](https://github.com/tokio-rs/tokio/pull/1)"];
+        let mut checker = Checker::new("draft.md", &lines, &context);
+        checker.check_pr_list(0, &lines);
+        let rules = checker
+            .diagnostics
+            .iter()
+            .map(|d| d.rule)
+            .collect::<Vec<_>>();
+        assert_eq!(rules, ["pr-org", "pr-not-merged"]);
+    }
+}
@@ -0,0 +1,227 @@
+//! Validators for the standard sections besides the Rust updates
+
+use super::{heading_name, Chapter, Checker};
+use time::{macros::format_description, Date};
+
+/// a date as TWiR writes it
+fn is_date(text: &str) -> bool {
+    Date::parse(text.trim(), format_description!("[year]-[month]-[day]")).is_ok()
+}
+
+/// a date or a `from - until` range of dates
+fn is_date_range(text: &str) -> bool {
+    match text.split_once(" - ") {
+        Some((from, until)) => is_date(from) && is_date(until),
+        None => is_date(text),
+    }
+}
+
+/// the standard wording for nothing to report, e.g.
+/// `* *No RFCs were approved this week.*`
+fn is_empty_notice(line: &str) -> bool {
+    let line = line.trim();
+    let line = line.strip_prefix("* ").unwrap_or(line).trim();
+    line.len() > 2 && line.starts_with("*No ") && line.ends_with('*') && line.contains(" this week")
+}
+
+/// the byte offset of the target of the first markdown link in `line`
+fn link_target(line: &str) -> Option<(usize, &str)> {
+    let start = line.find("](")? + 2;
+    let end = start + line[start..].find(')')?;
+    Some((start, &line[start..end]))
+}
+
+impl Checker<'_> {
+    /// the top-level list items of a chapter with their line index
+    fn items<'c>(chapter: &'c Chapter<'_>) -> impl Iterator<Item = (usize, &'c str)> + 'c {
+        (chapter.start..)
+            .zip(chapter.lines.iter().copied())
+            .skip(1)
+            .filter(|(_, l)| l.starts_with("* ") || l.starts_with("- "))
+    }
+
+    /// a list of links, or the standard notice that there's nothing this week
+    pub(super) fn check_link_list(&mut self, chapter: &Chapter<'_>, target: Option<&str>) {
+        let mut items = 0;
+        for (i, item) in Self::items(chapter) {
+            items += 1;
+            if is_empty_notice(item) {
+                continue;
+            }
+            match link_target(item) {
+                Some((offset, link)) => {
+                    if let Some(target) = target.filter(|t| !link.starts_with(t)) {
+                        self.error(
+                            i,
+                            offset,
+                            "section-link",
+                            format!("expected a link to {target}"),
+                        );
+                    }
+                }
+                None => self.error(i, 0, "section-entry", "expected a link".into()),
+            }
+        }
+        let notices = chapter.lines.iter().filter(|l| is_empty_notice(l)).count();
+        if items == 0 && notices == 0 {
+            self.error(
+                chapter.start,
+                0,
+                "empty-section",
+                format!(
+                    "`{}` has no entries, say so with the usual `*No ... this week.*`",
+                    heading_name(chapter.title)
+                ),
+            );
+        }
+    }
+
+    /// `Rusty Events between <date> - <date>`
+    pub(super) fn check_events(&mut self, chapter: &Chapter<'_>) {
+        let header = (chapter.start..)
+            .zip(chapter.lines)
+            .find_map(|(i, l)| Some((i, l.strip_prefix("Rusty Events between ")?)));
+        match header {
+            Some((i, range)) => {
+                let range = range.trim_end_matches('🦀').trim();
+                if !is_date_range(range) {
+                    self.error(
+                        i,
+                        "Rusty Events between ".len(),
+                        "events-dates",
+                        "expected `YYYY-MM-DD - YYYY-MM-DD`".into(),
+                    );
+                }
+            }
+            None => self.error(
+                chapter.start,
+                0,
+                "events-dates",
+                "missing `Rusty Events between <from> - <until>`".into(),
+            ),
+        }
+    }
+
+    /// `* <date> | <place> | [organizer](link)`, then the events as a
+    /// nested list of links
+    pub(super) fn check_event_region(&mut self, chapter: &Chapter<'_>) {
+        for (i, line) in (chapter.start..).zip(chapter.lines).skip(1) {
+            let trimmed = line.trim_start();
+            if !(trimmed.starts_with("* ") || trimmed.starts_with("- ")) || is_empty_notice(line) {
+                continue;
+            }
+            if trimmed.len() < line.len() {
+                if link_target(line).is_none() {
+                    self.error(i, 0, "event-entry", "expected a link to the event".into());
+                }
+                continue;
+            }
+            let fields = line[2..].split(" | ").collect::<Vec<_>>();
+            if fields.len() < 3 {
+                self.error(
+                    i,
+                    0,
+                    "event-entry",
+                    "expected `* <date> | <place> | [organizer](link)`".into(),
+                );
+            } else if !is_date_range(fields[0]) {
+                self.error(
+                    i,
+                    2,
+                    "event-date",
+                    format!("`{}` is not a `YYYY-MM-DD` date", fields[0].trim()),
+                );
+            } else if link_target(fields[fields.len() - 1]).is_none() {
+                self.error(
+                    i,
+                    0,
+                    "event-entry",
+                    "expected a link to the organizer".into(),
+                );
+            }
+        }
+    }
+
+    /// the revision range and a link to the full report
+    pub(super) fn check_triage(&mut self, chapter: &Chapter<'_>) {
+        if chapter.lines.iter().any(|l| is_empty_notice(l)) {
+            return;
+        }
+        let lines = (chapter.start..).zip(chapter.lines.iter().copied());
+        let range = lines
+            .clone()
+            .find(|(_, l)| l.starts_with("Revision range:"));
+        match range {
+            Some((i, line)) => {
+                if !link_target(line)
+                    .is_some_and(|(_, l)| l.starts_with("https://perf.rust-lang.org/"))
+                {
+                    self.error(
+                        i,
+                        0,
+                        "triage-range",
+                        "expected a link to perf.rust-lang.org".into(),
+                    );
+                }
+            }
+            None => self.error(
+                chapter.start,
+                0,
+                "triage-range",
+                "missing `Revision range: [..](https://perf.rust-lang.org/..)`".into(),
+            ),
+        }
+        const REPORTS: &str = "https://github.com/rust-lang/rustc-perf/blob/master/triage/";
+        let report = lines
+            .filter_map(|(i, l)| Some((i, link_target(l)?)))
+            .find(|(_, (_, l))| l.starts_with(REPORTS));
+        match report {
+            Some((i, (offset, link))) => {
+                // newer reports are in a directory per year
+                let name = &link[REPORTS.len()..];
+                let (year, file) = match name.split_once('/') {
+                    Some((year, file)) => (Some(year), file),
+                    None => (None, name),
+                };
+                let date = file.strip_suffix(".md").unwrap_or(file);
+                if !is_date(date) {
+                    self.error(
+                        i,
+                        offset + REPORTS.len(),
+                        "triage-report",
+                        "expected the report to be named `[YYYY/]YYYY-MM-DD.md`".into(),
+                    );
+                } else if let Some(year) = year.filter(|y| !date.starts_with(&format!("{y}-"))) {
+                    self.error(
+                        i,
+                        offset + REPORTS.len(),
+                        "triage-report",
+                        format!("the report of {date} is in the directory of {year}"),
+                    );
+                }
+            }
+            None => self.error(
+                chapter.start,
+                0,
+                "triage-report",
+                "missing the link to the full report".into(),
+            ),
+        }
+    }
+
+    /// a link to the current who's hiring thread
+    pub(super) fn check_jobs(&mut self, chapter: &Chapter<'_>) {
+        let linked = chapter
+            .lines
+            .iter()
+            .any(|l| l.contains("](https://www.reddit.com/r/rust/"));
+        if !linked {
+            self.error(
+                chapter.start,
+                0,
+                "jobs-link",
+                "missing the link to the who's hiring thread on r/rust".into(),
+            );
+        }
+    }
+}
@@ -0,0 +1,626 @@
+use crate::rules::Rule;
+use crate::week::Cutoff;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use twirer::title::{CodeRule, TitleOptions};
+
+pub static CONFIG: &str = "cache/config.toml";
+pub static LEGACY_CONFIG: &str = "cache/config";
+
+/// Our configuration, read from `cache/config.toml`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// drop PRs whose (lowercased) list entry contains any of these
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// sort PRs from these repos first, in this order
+    #[serde(default)]
+    pub order: Vec<String>,
+    /// decide what to do with PRs, the first matching rule wins
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// words that are always code
+    #[serde(default)]
+    pub code_keywords: Vec<String>,
+    /// code detection rules to switch off, e.g. `camel_case`
+    #[serde(default)]
+    pub disabled_code_rules: Vec<CodeRule>,
+    pub editor: Option<String>,
+    pub firefox: Option<String>,
+    /// read recorded search responses from here instead of asking GitHub
+    pub fixtures: Option<PathBuf>,
+    /// read the GitHub token from this file, `cache/token` by default
+    pub token_file: Option<PathBuf>,
+    /// how often to retry a search on rate limits or server errors
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// the git remote of our this-week-in-rust fork
+    #[serde(default = "default_remote")]
+    pub remote: String,
+    /// our this-week-in-rust fork on GitHub, as `owner/repo`
+    #[serde(default = "default_fork")]
+    pub fork: String,
+    /// our checkout of this-week-in-rust
+    #[serde(default = "default_twir")]
+    pub twir: PathBuf,
+    /// the weekday the week of an issue ends on, the last one before its date
+    #[serde(default = "default_week_ends_on")]
+    pub week_ends_on: String,
+    /// the time of day at which one week ends and the next starts, `HH:MM`
+    #[serde(default = "default_cutoff")]
+    pub cutoff: String,
+    /// the UTC offset of `cutoff`, e.g. `+02:00`; it's fixed, named zones
+    /// like `Europe/Berlin` aren't supported
+    #[serde(default = "default_time_zone")]
+    pub time_zone: String,
+    /// the searches whose results we merge, the first one is linked as `[merged]`
+    #[serde(default = "default_queries")]
+    pub queries: Vec<Query>,
+    /// the subsections of the updates, in order
+    #[serde(default = "default_sections")]
+    pub sections: Vec<Section>,
+    /// per-repo options keyed by `org/repo`, these replace the built-in ones
+    #[serde(default)]
+    pub repos: BTreeMap<String, RepoOptions>,
+}
+
+/// A GitHub search for merged PRs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Query {
+    #[serde(default)]
+    pub orgs: Vec<String>,
+    /// as `org/repo`
+    #[serde(default)]
+    pub repos: Vec<String>,
+    /// anything else to put in the search, e.g. `-label:rollup`, `-author:bors`
+    #[serde(default)]
+    pub qualifiers: Vec<String>,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Query {
+            orgs: vec!["rust-lang".into()],
+            repos: Vec::new(),
+            qualifiers: Vec::new(),
+        }
+    }
+}
+
+impl Query {
+    /// the search string for PRs merged within `week_spec`
+    pub fn search(&self, week_spec: &str) -> String {
+        let mut search = vec!["is:pr".to_owned()];
+        search.extend(self.orgs.iter().map(|o| format!("org:{o}")));
+        search.extend(self.repos.iter().map(|r| format!("repo:{r}")));
+        search.push("is:merged".into());
+        search.extend(self.qualifiers.iter().cloned());
+        search.push(format!("merged:{week_spec}"));
+        search.join(" ")
+    }
+}
+
+fn default_queries() -> Vec<Query> {
+    vec![Query::default()]
+}
+
+/// What to do with PRs from a given repository
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoOptions {
+    /// put `<prefix>: ` in front of the title (escaped as needed)
+    pub prefix: Option<String>,
+    /// the section of the updates its PRs go to by default
+    pub section: Option<String>,
+    /// drop all PRs from this repo
+    #[serde(default)]
+    pub ignore: bool,
+}
+
+/// the repos we had prefixed from the start, and their sections
+static DEFAULT_PREFIXES: &[(&str, &str, Option<&str>)] = &[
+    ("rust-lang/rust-clippy", "clippy", Some("Clippy")),
+    ("rust-lang/rustfmt", "rustfmt", None),
+    ("rust-lang/cargo", "cargo", Some("Cargo")),
+    ("rust-lang/rustc_codegen_gcc", "codegen_gcc", None),
+    ("rust-lang/futures-rs", "futures", None),
+    ("rust-lang/rustup", "rustup", None),
+    ("rust-lang/libc", "libc", None),
+    ("rust-lang/docs.rs", "docs.rs", None),
+    ("rust-lang/hashbrown", "hashbrown", None),
+    ("rust-lang/miri", "miri", None),
+    (
+        "rust-lang/rust-analyzer",
+        "rust-analyzer",
+        Some("Rust-Analyzer"),
+    ),
+    ("rust-lang/rust-bindgen", "bindgen", None),
+];
+
+/// A subsection of the Rust updates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Section {
+    pub name: String,
+    /// PRs with any of these labels go here
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// PRs whose title contains any of these (ignoring case) go here, unless
+    /// their repo has a section
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+impl Section {
+    fn new(name: &str, labels: &[&str], keywords: &[&str]) -> Self {
+        Section {
+            name: name.to_owned(),
+            labels: labels.iter().map(|&l| l.to_owned()).collect(),
+            keywords: keywords.iter().map(|&k| k.to_owned()).collect(),
+        }
+    }
+}
+
+fn default_sections() -> Vec<Section> {
+    vec![
+        Section::new("Compiler", &["T-compiler"], &["rustc_", "compiler/"]),
+        Section::new(
+            "Library",
+            &["T-libs", "T-libs-api"],
+            &["library/", "std::", "core::", "alloc::"],
+        ),
+        Section::new("Cargo", &[], &[]),
+        Section::new("Rustdoc", &["T-rustdoc"], &["rustdoc"]),
+        Section::new("Clippy", &[], &[]),
+        Section::new("Rust-Analyzer", &[], &[]),
+    ]
+}
+
+fn default_retries() -> u32 {
+    5
+}
+
+fn default_remote() -> String {
+    "llogiq".into()
+}
+
+fn default_week_ends_on() -> String {
+    "tuesday".into()
+}
+
+fn default_cutoff() -> String {
+    "00:00".into()
+}
+
+fn default_time_zone() -> String {
+    "UTC".into()
+}
+
+fn default_fork() -> String {
+    "llogiq/this-week-in-rust".into()
+}
+
+fn default_twir() -> PathBuf {
+    "../this-week-in-rust".into()
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            ignore: Vec::new(),
+            order: Vec::new(),
+            rules: Vec::new(),
+            code_keywords: Vec::new(),
+            disabled_code_rules: Vec::new(),
+            editor: None,
+            firefox: None,
+            fixtures: None,
+            token_file: None,
+            retries: default_retries(),
+            remote: default_remote(),
+            fork: default_fork(),
+            twir: default_twir(),
+            week_ends_on: default_week_ends_on(),
+            cutoff: default_cutoff(),
+            time_zone: default_time_zone(),
+            queries: default_queries(),
+            sections: default_sections(),
+            repos: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// read `cache/config.toml`, falling back to the legacy `cache/config`
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        if Path::new(CONFIG).exists() {
+            Self::from_toml(&fs::read_to_string(CONFIG)?)
+                .map_err(|e| format!("{CONFIG}: {e}").into())
+        } else if Path::new(LEGACY_CONFIG).exists() {
+            Self::from_legacy(&fs::read_to_string(LEGACY_CONFIG)?)
+        } else {
+            Err(format!("no config found, please create {CONFIG}").into())
+        }
+    }
+
+    /// the config, or the defaults if there is none at all; anything wrong
+    /// with a config we have is still an error
+    pub fn read_or_default() -> Result<Self, Box<dyn Error>> {
+        if !Path::new(CONFIG).exists() && !Path::new(LEGACY_CONFIG).exists() {
+            return Ok(Config::default());
+        }
+        let config = Self::read()?;
+        for problem in config.problems() {
+            eprintln!("warning: {problem}, see `twirer config check`");
+        }
+        Ok(config)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    /// parse the old `key=value` format, where lists are comma separated
+    pub fn from_legacy(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut config = Config::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("{LEGACY_CONFIG}:{}: expected `key=value`", i + 1))?;
+            let (key, value) = (key.trim(), value.trim());
+            let list = || {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect()
+            };
+            match key {
+                "ignore" => config.ignore = list(),
+                "order" => config.order = list(),
+                "code_keywords" => config.code_keywords = list(),
+                "disabled_code_rules" => {
+                    config.disabled_code_rules = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(|s| {
+                            s.parse()
+                                .map_err(|e| format!("{LEGACY_CONFIG}:{}: {e}", i + 1))
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                "editor" => config.editor = Some(value.into()),
+                "firefox" => config.firefox = Some(value.into()),
+                "fixtures" => config.fixtures = Some(value.into()),
+                "token_file" => config.token_file = Some(value.into()),
+                "retries" => {
+                    config.retries = value.parse().map_err(|e| {
+                        format!("{LEGACY_CONFIG}:{}: `retries` must be a number: {e}", i + 1)
+                    })?
+                }
+                "remote" => config.remote = value.into(),
+                "fork" => config.fork = value.into(),
+                "twir" => config.twir = value.into(),
+                "week_ends_on" => config.week_ends_on = value.into(),
+                "cutoff" => config.cutoff = value.into(),
+                "time_zone" => config.time_zone = value.into(),
+                _ => return Err(format!("{LEGACY_CONFIG}:{}: unknown key `{key}`", i + 1).into()),
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn to_toml(&self) -> Result<String, Box<dyn Error>> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /// things that parse, but are most likely wrong
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (key, list) in [
+            ("ignore", &self.ignore),
+            ("order", &self.order),
+            ("code_keywords", &self.code_keywords),
+        ] {
+            for (i, item) in list.iter().enumerate() {
+                if item.trim().is_empty() {
+                    problems.push(format!("`{key}` has an empty entry"));
+                } else if item.trim() != item {
+                    problems.push(format!("`{key}` entry {item:?} has surrounding whitespace"));
+                } else if list[..i].contains(item) {
+                    problems.push(format!("`{key}` contains {item:?} twice"));
+                }
+            }
+        }
+        for kw in &self.ignore {
+            if kw.to_lowercase() != *kw {
+                problems.push(format!(
+                    "`ignore` entry {kw:?} is matched against lowercase text, but isn't lowercase"
+                ));
+            }
+        }
+        for (i, rule) in self.rules.iter().enumerate() {
+            if rule.is_empty() {
+                problems.push(format!(
+                    "rule {} has no criteria, it matches every PR",
+                    i + 1
+                ));
+            }
+            if let Some(Err(e)) = rule.title.as_deref().map(regex::Regex::new) {
+                problems.push(format!("rule {}: {e}", i + 1));
+            }
+        }
+        if let Err(e) = crate::week::parse_weekday(&self.week_ends_on) {
+            problems.push(format!("`week_ends_on`: {e}"));
+        }
+        if let Err(e) = self.cutoff() {
+            problems.push(format!("`cutoff`/`time_zone`: {e}"));
+        }
+        if self.queries.is_empty() {
+            problems.push("`queries` is empty, we won't find any PRs".into());
+        }
+        for (i, query) in self.queries.iter().enumerate() {
+            if query.orgs.is_empty() && query.repos.is_empty() {
+                problems.push(format!(
+                    "query {} has neither `orgs` nor `repos`, it will search all of GitHub",
+                    i + 1
+                ));
+            }
+        }
+        for (key, options) in &self.repos {
+            if let Some(section) = &options.section {
+                if !self.sections.iter().any(|s| s.name == *section) {
+                    problems.push(format!(
+                        "`repos.{key:?}` has section {section:?}, which is not in `sections`"
+                    ));
+                }
+            }
+        }
+        for key in self.repos.keys() {
+            if key.split('/').count() != 2 {
+                problems.push(format!("`repos` key {key:?} should be `org/repo`"));
+            }
+        }
+        if self.editor.is_none() {
+            problems.push("`editor` is missing, `start` and `editor` won't work".into());
+        }
+        if self.firefox.is_none() {
+            problems.push("`firefox` is missing, `start` and `push` won't work".into());
+        }
+        problems
+    }
+
+    /// the options for `org/repo`, either configured or built in
+    pub fn repo(&self, org: &str, repo: &str) -> Option<RepoOptions> {
+        let key = format!("{org}/{repo}");
+        if let Some(options) = self.repos.get(&key) {
+            return Some(options.clone());
+        }
+        DEFAULT_PREFIXES
+            .iter()
+            .find(|(r, _, _)| *r == key)
+            .map(|(_, prefix, section)| RepoOptions {
+                prefix: Some((*prefix).to_owned()),
+                section: section.map(ToOwned::to_owned),
+                ..RepoOptions::default()
+            })
+    }
+
+    /// how to format titles
+    pub fn title_options(&self) -> TitleOptions {
+        TitleOptions {
+            code_keywords: self.code_keywords.iter().cloned().collect(),
+            disabled_rules: self.disabled_code_rules.iter().copied().collect(),
+            prefixes: DEFAULT_PREFIXES
+                .iter()
+                .map(|(_, prefix, _)| (*prefix).to_owned())
+                .chain(self.repos.values().filter_map(|r| r.prefix.clone()))
+                .collect(),
+        }
+    }
+
+    /// the orgs our queries search in, including those of single repos
+    pub fn orgs(&self) -> Vec<String> {
+        let mut orgs = Vec::new();
+        for query in &self.queries {
+            let repo_orgs = query.repos.iter().filter_map(|r| r.split_once('/'));
+            for org in query
+                .orgs
+                .iter()
+                .map(|o| &o[..])
+                .chain(repo_orgs.map(|(o, _)| o))
+            {
+                if !orgs.iter().any(|o| o == org) {
+                    orgs.push(org.to_owned());
+                }
+            }
+        }
+        orgs
+    }
+
+    /// when one week ends and the next starts
+    pub fn cutoff(&self) -> Result<Cutoff, Box<dyn Error>> {
+        Cutoff::parse(&self.cutoff, &self.time_zone)
+    }
+
+    /// the query we link to as `[merged]`
+    pub fn link_query(&self) -> Query {
+        self.queries.first().cloned().unwrap_or_default()
+    }
+
+    pub fn editor(&self) -> Result<&str, Box<dyn Error>> {
+        self.editor
+            .as_deref()
+            .ok_or_else(|| format!("needs `editor = \"<path>\"` in {CONFIG}").into())
+    }
+
+    pub fn firefox(&self) -> Result<&str, Box<dyn Error>> {
+        self.firefox
+            .as_deref()
+            .ok_or_else(|| format!("needs `firefox = \"<path>\"` in {CONFIG}").into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_migrates() {
+        let legacy = "ignore=needless, typo\n\norder = rust-lang/rust,rust-lang/cargo\n\
+                      disabled_code_rules=path,macro\neditor=/usr/bin/vim\nremote=origin\nretries=2\n";
+        let config = Config::from_legacy(legacy).unwrap();
+        assert_eq!(config.ignore, ["needless", "typo"]);
+        assert_eq!(config.order, ["rust-lang/rust", "rust-lang/cargo"]);
+        assert_eq!(
+            config.disabled_code_rules,
+            [CodeRule::Path, CodeRule::Macro]
+        );
+        assert_eq!(config.editor.as_deref(), Some("/usr/bin/vim"));
+        assert_eq!(config.remote, "origin");
+        assert_eq!(config.retries, 2);
+        // what `config migrate` writes reads back the same
+        let migrated = Config::from_toml(&config.to_toml().unwrap()).unwrap();
+        assert_eq!(migrated.to_toml().unwrap(), config.to_toml().unwrap());
+        assert_eq!(migrated.fork, "llogiq/this-week-in-rust");
+        assert_eq!(migrated.sections.len(), 6);
+        assert_eq!(migrated.week_ends_on, "tuesday");
+    }
+
+    #[test]
+    fn legacy_errors() {
+        let error = |text| Config::from_legacy(text).unwrap_err().to_string();
+        assert_eq!(
+            error("ignore=a\nnonsense"),
+            "cache/config:2: expected `key=value`"
+        );
+        assert_eq!(error("colour=blue"), "cache/config:1: unknown key `colour`");
+        assert_eq!(
+            error("disabled_code_rules=path,pathh"),
+            "cache/config:1: unknown code rule `pathh`"
+        );
+        assert!(error("retries=many").starts_with("cache/config:1: `retries` must be a number"));
+    }
+
+    #[test]
+    fn toml_errors() {
+        let error = |text| Config::from_toml(text).unwrap_err().to_string();
+        assert!(error("ignroe = []").contains("unknown field `ignroe`"));
+        assert!(error("ignore = \"needless\"").contains("invalid type"));
+        assert!(error("[[rules]]\naction = \"skip\"").contains("unknown variant `skip`"));
+    }
+
+    #[test]
+    fn problems() {
+        let config = Config::from_toml(
+            r#"
+            ignore = ["Needless", " typo", "typo", "", "typo"]
+            editor = "vim"
+            firefox = "firefox"
+            week_ends_on = "tuesdays"
+            time_zone = "CEST"
+
+            [[rules]]
+            action = "drop"
+
+            [[rules]]
+            action = "flag"
+            title = "("
+
+            [[queries]]
+
+            [repos."rust-lang/miri"]
+            section = "Miri"
+
+            [repos.rustup]
+            prefix = "rustup"
+            "#,
+        )
+        .unwrap();
+        let problems = config.problems();
+        let expected = [
+            "`ignore` entry \" typo\" has surrounding whitespace",
+            "`ignore` has an empty entry",
+            "`ignore` contains \"typo\" twice",
+            "`ignore` entry \"Needless\" is matched against lowercase text, but isn't lowercase",
+            "rule 1 has no criteria, it matches every PR",
+            "`week_ends_on`: `tuesdays` is not a weekday",
+            "query 1 has neither `orgs` nor `repos`, it will search all of GitHub",
+            "`repos.\"rust-lang/miri\"` has section \"Miri\", which is not in `sections`",
+            "`repos` key \"rustup\" should be `org/repo`",
+        ];
+        for problem in expected {
+            assert!(
+                problems.iter().any(|p| p == problem),
+                "{problem}: {problems:#?}"
+            );
+        }
+        assert!(problems
+            .iter()
+            .any(|p| p.starts_with("rule 2: regex parse error")));
+        assert!(problems
+            .iter()
+            .any(|p| p.starts_with("`cutoff`/`time_zone`: `CEST` is a named time zone")));
+        assert_eq!(problems.len(), expected.len() + 2, "{problems:#?}");
+        // the defaults only lack the local paths
+        assert_eq!(
+            Config::default().problems(),
+            [
+                "`editor` is missing, `start` and `editor` won't work",
+                "`firefox` is missing, `start` and `push` won't work"
+            ]
+        );
+    }
+
+    #[test]
+    fn repo_options() {
+        let config = Config::from_toml("[repos.\"rust-lang/cargo\"]\nignore = true\n").unwrap();
+        // configured options replace the built-in ones
+        let cargo = config.repo("rust-lang", "cargo").unwrap();
+        assert!(cargo.ignore && cargo.prefix.is_none());
+        let clippy = config.repo("rust-lang", "rust-clippy").unwrap();
+        assert_eq!(clippy.prefix.as_deref(), Some("clippy"));
+        assert_eq!(clippy.section.as_deref(), Some("Clippy"));
+        assert!(config.repo("rust-lang", "rust").is_none());
+    }
+
+    #[test]
+    fn queries() {
+        let config = Config::from_toml(
+            "[[queries]]\norgs = [\"rust-lang\"]\nqualifiers = [\"-author:bors\"]\n\n\
+             [[queries]]\nrepos = [\"rust-lang/rustup\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.link_query().search("2026-10-13..2026-10-19"),
+            "is:pr org:rust-lang is:merged -author:bors merged:2026-10-13..2026-10-19"
+        );
+        assert_eq!(
+            config.queries[1].search("2026-10-13"),
+            "is:pr repo:rust-lang/rustup is:merged merged:2026-10-13"
+        );
+        assert_eq!(config.orgs(), ["rust-lang"]);
+        let config = Config::from_toml(
+            "[[queries]]\nrepos = [\"tokio-rs/tokio\", \"rust-lang/rust\"]\n\n\
+             [[queries]]\norgs = [\"rust-lang\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.orgs(), ["tokio-rs", "rust-lang"]);
+        let empty = Config::from_toml("queries = []").unwrap();
+        assert!(empty
+            .problems()
+            .contains(&"`queries` is empty, we won't find any PRs".to_owned()));
+        assert_eq!(empty.link_query().orgs, ["rust-lang"]);
+    }
+}
@@ -0,0 +1,344 @@
+use crate::check::{self, chapters};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+static HISTORY: &str = "cache/history.json";
+/// what `filter` dropped from the current issue, and why
+pub static DROPPED: &str = "cache/dropped.json";
+
+/// bump this whenever the layout of `History` changes incompatibly
+const VERSION: u32 = 1;
+
+/// Every issue we know of
+#[derive(Serialize, Deserialize)]
+pub struct History {
+    version: u32,
+    pub issues: Vec<Issue>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            version: VERSION,
+            issues: Vec::new(),
+        }
+    }
+}
+
+/// The PRs of one published issue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Issue {
+    pub number: u64,
+    /// the week spec it covered, if we know it
+    #[serde(default)]
+    pub week: Option<String>,
+    pub included: Vec<String>,
+    /// the PRs we had, but left out
+    #[serde(default)]
+    pub dropped: Vec<Dropped>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dropped {
+    pub url: String,
+    pub reason: String,
+}
+
+/// how a PR turns up in the history
+pub enum Found<'h> {
+    Included(&'h Issue),
+    Dropped(&'h Issue, &'h str),
+}
+
+/// whether two links point to the same PR
+fn same_pr(a: &str, b: &str) -> bool {
+    match (check::pr_key(a), check::pr_key(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a.trim_end_matches('/') == b.trim_end_matches('/'),
+    }
+}
+
+impl History {
+    /// the history, empty if we don't have one yet
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        if !Path::new(HISTORY).exists() {
+            return Ok(History::default());
+        }
+        let history: History = serde_json::from_str(&fs::read_to_string(HISTORY)?)?;
+        if history.version != VERSION {
+            return Err(format!(
+                "{HISTORY} has version {}, expected {VERSION}",
+                history.version
+            )
+            .into());
+        }
+        Ok(history)
+    }
+
+    pub fn write(&self) -> Result<(), Box<dyn Error>> {
+        let _ = fs::create_dir_all("cache"); // ignore possible errors
+        fs::write(HISTORY, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// add an issue, replacing any earlier record with the same number
+    pub fn record(&mut self, issue: Issue) {
+        self.issues.retain(|i| i.number != issue.number);
+        let at = self.issues.partition_point(|i| i.number < issue.number);
+        self.issues.insert(at, issue);
+    }
+
+    /// every PR any issue included
+    pub fn urls(&self) -> HashSet<String> {
+        self.issues
+            .iter()
+            .flat_map(|i| i.included.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// the last issue that dropped the PR at `url`, and why
+    pub fn dropped(&self, url: &str) -> Option<(&Issue, &str)> {
+        self.find(url)
+            .into_iter()
+            .rev()
+            .find_map(|found| match found {
+                Found::Dropped(issue, reason) => Some((issue, reason)),
+                Found::Included(_) => None,
+            })
+    }
+
+    /// the issues that included or dropped the PR at `url`
+    pub fn find(&self, url: &str) -> Vec<Found<'_>> {
+        let mut found = Vec::new();
+        for issue in &self.issues {
+            if issue.included.iter().any(|u| same_pr(u, url)) {
+                found.push(Found::Included(issue));
+            }
+            if let Some(d) = issue.dropped.iter().find(|d| same_pr(&d.url, url)) {
+                found.push(Found::Dropped(issue, &d.reason));
+            }
+        }
+        found
+    }
+}
+
+/// the PR links in the Rust updates of an issue
+pub fn updates_links(issue: &str) -> Vec<String> {
+    let lines = issue.lines().collect::<Vec<_>>();
+    let mut links = Vec::new();
+    let mut in_updates = false;
+    for chapter in chapters(&lines) {
+        in_updates = match (chapter.level, chapter.title) {
+            // older issues have the core updates
+            (_, "Updates from the Rust Project" | "Updates from Rust Core") => true,
+            // `####` subsections of the updates, up to the RFCs and such
+            (4, title) => in_updates && !check::NOT_PR_LISTS.contains(&check::heading_name(title)),
+            _ => false,
+        };
+        if !in_updates {
+            continue;
+        }
+        for line in chapter.lines {
+            let link = line
+                .trim_start()
+                .strip_prefix("* [")
+                .and_then(|l| l.trim_end().strip_suffix(')'))
+                .and_then(|l| l.rsplit_once("]("))
+                .map(|(_, link)| link);
+            if let Some(link) = link.filter(|l| check::pr_key(l).is_some()) {
+                links.push(link.to_owned());
+            }
+        }
+    }
+    links
+}
+
+/// the number of a published issue, from its `Number:` or `Title:`
+fn issue_number(issue: &str) -> Option<u64> {
+    let header = |key| issue.lines().find_map(|l| l.strip_prefix(key));
+    match header("Number: ") {
+        Some(n) => n.trim().parse().ok(),
+        None => header("Title: This Week in Rust ")?.trim().parse().ok(),
+    }
+}
+
+/// the `merged:` range of the `[merged]` link, if there is one
+fn merged_range(issue: &str) -> Option<String> {
+    let link = issue
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("[merged]:"))?;
+    // the link is url encoded
+    let mut decoded = Vec::new();
+    let mut bytes = link.trim().bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'%' => {
+                let hex = [bytes.next()?, bytes.next()?];
+                decoded.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            b'+' => decoded.push(b' '),
+            b => decoded.push(b),
+        }
+    }
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (_, range) = decoded.split_once("merged:")?;
+    Some(range.split(['&', ' ']).next()?.to_owned())
+}
+
+/// How an import went
+#[derive(Default)]
+pub struct Imported {
+    pub issues: usize,
+    /// issues we already had, which we keep as they are
+    pub known: usize,
+    /// files that don't look like an issue with Rust updates
+    pub skipped: Vec<String>,
+}
+
+/// add the published issues in `dir` we don't have yet
+pub fn import(history: &mut History, dir: &Path) -> Result<Imported, Box<dyn Error>> {
+    let mut imported = Imported::default();
+    let mut paths = fs::read_dir(dir)
+        .map_err(|e| format!("{}: {e}", dir.display()))?
+        .filter_map(|e| Some(e.ok()?.path()))
+        .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("md")))
+        .collect::<Vec<_>>();
+    paths.sort();
+    for path in paths {
+        let text = fs::read_to_string(&path)?;
+        let included = updates_links(&text);
+        let Some(number) = issue_number(&text).filter(|_| !included.is_empty()) else {
+            imported.skipped.push(path.display().to_string());
+            continue;
+        };
+        if history.issues.iter().any(|i| i.number == number) {
+            imported.known += 1;
+            continue;
+        }
+        history.record(Issue {
+            number,
+            week: merged_range(&text),
+            included,
+            dropped: Vec::new(),
+        });
+        imported.issues += 1;
+    }
+    Ok(imported)
+}
+
+pub fn read_dropped() -> Vec<Dropped> {
+    fs::read_to_string(DROPPED)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+pub fn write_dropped(dropped: &[Dropped]) -> Result<(), Box<dyn Error>> {
+    fs::write(DROPPED, serde_json::to_string_pretty(dropped)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static ISSUE: &str = "\
+Title: This Week in Rust 600
+Number: 600
+Date: 2026-10-21
+
+## Updates from the Rust Project
+
+2 pull requests were [merged in the last week][merged]
+
+[merged]: https://github.com/search?q=is%3Apr+org%3Arust-lang+is%3Amerged+merged%3A2026-10-13T00%3A00%3A00Z..2026-10-19T23%3A59%3A59Z&type=pullRequests
+
+#### Compiler
+
+* [stabilize `let_chains`](https://github.com/rust-lang/rust/pull/1)
+
+#### Library
+
+* [add `Vec::pop_if`](https://github.com/rust-lang/rust/pull/2)
+
+#### Rust Compiler Performance Triage
+
+* [a regression](https://github.com/rust-lang/rust/pull/10)
+
+#### [Approved RFCs](https://github.com/rust-lang/rfcs/commits/master)
+
+* [RFC: feature](https://github.com/rust-lang/rfcs/pull/3000)
+
+#### Final Comment Period
+
+##### [Tracking Issues & PRs](https://github.com/rust-lang/rust/issues)
+
+* [Stabilize `foo`](https://github.com/rust-lang/rust/pull/11)
+
+## Call for Testing
+
+* [not an update](https://github.com/rust-lang/rust/pull/12)
+";
+
+    #[test]
+    fn only_updates() {
+        assert_eq!(
+            updates_links(ISSUE),
+            [
+                "https://github.com/rust-lang/rust/pull/1",
+                "https://github.com/rust-lang/rust/pull/2",
+            ]
+        );
+    }
+
+    #[test]
+    fn issue_header() {
+        assert_eq!(issue_number(ISSUE), Some(600));
+        assert_eq!(issue_number("Title: This Week in Rust 12\n"), Some(12));
+        assert_eq!(issue_number("Title: Something else\n"), None);
+        assert_eq!(
+            merged_range(ISSUE).as_deref(),
+            Some("2026-10-13T00:00:00Z..2026-10-19T23:59:59Z")
+        );
+        assert_eq!(
+            merged_range("[merged]: https://github.com/search?q=is%3Apr"),
+            None
+        );
+    }
+
+    #[test]
+    fn import_keeps_known_issues() {
+        let dir = std::env::temp_dir().join(format!("twirer-import-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("2026-10-21-this-week-in-rust.md"), ISSUE).unwrap();
+        fs::write(
+            dir.join("2015-01-01-old.md"),
+            "Title: This Week in Rust 1\n",
+        )
+        .unwrap();
+        let mut history = History::default();
+        history.record(Issue {
+            number: 599,
+            week: None,
+            included: Vec::new(),
+            dropped: Vec::new(),
+        });
+        let imported = import(&mut history, &dir).unwrap();
+        assert_eq!((imported.issues, imported.known), (1, 0));
+        assert_eq!(imported.skipped.len(), 1);
+        assert_eq!(history.issues.len(), 2);
+        // a second import leaves what we have alone
+        history.issues[1].dropped.push(Dropped {
+            url: "https://github.com/rust-lang/rust/pull/3".into(),
+            reason: "rule 1".into(),
+        });
+        let imported = import(&mut history, &dir).unwrap();
+        assert_eq!((imported.issues, imported.known), (0, 1));
+        assert_eq!(history.issues[1].dropped.len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
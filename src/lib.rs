@@ -0,0 +1,3 @@
+//! The parts of twirer that are useful on their own
+
+pub mod title;
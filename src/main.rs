@@ -1,92 +1,194 @@
-use octocrab::Octocrab;
+mod cache;
+mod check;
+mod config;
+mod history;
+mod rules;
+mod source;
+mod token;
+mod week;
+
+use cache::PullRequest;
+use check::Checker;
+use config::Config;
+use history::{Dropped, Found, History};
+use rules::{Action, Rules};
+use source::PrSource;
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::error::Error;
-use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::{env, fs, io};
-use time::{macros::format_description, Date, Duration};
+use twirer::title::{escape_markdown, explain_title, format_title};
+use week::{Cutoff, Week};
 
 static WORKSPACE: &str = env!("CARGO_MANIFEST_DIR");
-static TWIR: &str = "../this-week-in-rust";
 
-type Config = HashMap<String, String>;
+fn repo_order<'p>(pr: &'p PullRequest, order: &[&str]) -> (usize, &'p str) {
+    (
+        order
+            .iter()
+            .position(|&r| r == pr.repo)
+            .unwrap_or(usize::MAX),
+        &pr.repo,
+    )
+}
 
-fn read_config() -> Result<Config, Box<dyn Error>> {
-    let config = fs::read_to_string("cache/config")?;
-    Ok(config
-        .split('\n')
-        .flat_map(|s| s.trim_end().split_once("="))
-        .map(|(k, v)| (k.into(), v.into()))
-        .collect())
+/// print the week with its link and whatever looks wrong about it
+fn show_week(week: &Week, config: &Config, cutoff: &Cutoff) {
+    println!("{week}");
+    println!("{}", merged_link(config, &week.query()));
+    for problem in week.problems(week::read_last(cutoff).as_ref()) {
+        println!("warning: {problem}");
+    }
 }
 
-fn get_list<'c>(config: &'c Config, key: &str) -> Result<Vec<&'c str>, Box<dyn Error>> {
-    Ok(config
-        .get(key)
-        .ok_or_else(|| Cow::Owned(format!("missing `{key}=a, b, c` in config")))?
-        .split(", ")
-        .collect())
+/// ask GitHub (or the fixtures) about our token, without showing it
+async fn check_token(config: &Config, fixtures: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    if fixtures.or(config.fixtures.as_deref()).is_none() {
+        println!("using the token from {}", token::token(config)?.1);
+    }
+    let info = pr_source(config, fixtures)?.token_info().await?;
+    println!("the token belongs to {}", info.login);
+    match &info.scopes {
+        None => println!("it is a fine-grained token"),
+        Some(scopes) if scopes.is_empty() => println!("it has no scopes"),
+        Some(scopes) => println!("it has the scopes {}", scopes.join(", ")),
+    }
+    if let Some(expires) = &info.expires {
+        println!("it expires at {expires}");
+    }
+    for problem in token::problems(&info, time::OffsetDateTime::now_utc()) {
+        println!("warning: {problem}");
+    }
+    Ok(())
 }
 
-fn repo_title<'l>(line: &'l str, order: &[&str]) -> (usize, &'l str, &'l str) {
-    line.rsplit_once("](")
-        .map_or((usize::MAX, "", line), |(title, href)| {
-            let repo = href[10..].split('/').nth(2).unwrap_or("");
-            (
-                order.iter().position(|&r| r == repo).unwrap_or(usize::MAX),
-                repo,
-                title,
-            )
-        })
+/// choose where to get PRs from: recorded fixtures if given on the command
+/// line or as `fixtures = "<dir>"` in the config, GitHub otherwise
+fn pr_source(
+    config: &Config,
+    fixtures: Option<&Path>,
+) -> Result<Box<dyn PrSource>, Box<dyn Error>> {
+    if let Some(dir) = fixtures.or(config.fixtures.as_deref()) {
+        return Ok(Box::new(source::Fixtures::new(dir)));
+    }
+    let (token, _) = token::token(config)?;
+    Ok(Box::new(source::GitHub::new(token)?))
 }
 
-fn week() -> Result<String, Box<dyn Error>> {
-    let current = fs::read_to_string("cache/week_spec")?;
-    let until = current.trim().splitn(2, "..").nth(1).unwrap();
-    let date_format = format_description!("[year]-[month]-[day]");
-    let date = Date::parse(until, date_format)?;
-    let next_date = date + Duration::WEEK;
-    let result = format!(
-        "{}..{}",
-        date.format(date_format)?,
-        next_date.format(date_format)?,
-    );
-    Ok(result)
+/// the GitHub search page for `query`
+fn search_url(query: &str) -> String {
+    format!(
+        "https://github.com/search?q={}",
+        source::encode_query(query)
+    )
 }
 
-fn token() -> Result<String, Box<dyn Error>> {
-    if let Ok(token) = env::var("GH_TOKEN") {
-        return Ok(token);
-    }
-    let mut out = io::stdout();
-    out.write(b"token: ")?;
-    out.flush()?;
-    let mut token = String::new();
-    std::io::stdin().read_line(&mut token)?;
-    if token.ends_with('\n') {
-        let _newline = token.pop();
-        if token.ends_with("\r") {
-            let _cr = token.pop();
+/// the link behind `[merged]` for the given `merged:` range
+fn merged_link(config: &Config, range: &str) -> String {
+    search_url(&config.link_query().search(range))
+}
+
+/// get all pages of a search, continuing where an earlier run left off
+async fn fetch(
+    source: &dyn PrSource,
+    config: &Config,
+    spec: &str,
+    progress: &mut cache::Progress,
+) -> Result<(u64, Vec<PullRequest>), Box<dyn Error>> {
+    loop {
+        let search = progress.searches.entry(spec.to_owned()).or_default();
+        if search.complete {
+            return Ok((search.total, search.prs.clone()));
+        }
+        let page = source::search_retrying(source, spec, search.pages + 1, config.retries).await?;
+        search.pages += 1;
+        search.total = page.total_count;
+        search.complete = !page.has_next;
+        search
+            .prs
+            .extend(page.items.iter().filter_map(PullRequest::from_issue));
+        print!(
+            "\rfetched page {} ({}/{} PRs)",
+            search.pages,
+            search.prs.len(),
+            search.total
+        );
+        if search.complete {
+            println!();
         }
+        io::stdout().flush()?;
+        cache::write_progress(progress)?;
     }
-    Ok(token)
 }
 
-async fn prs(week_spec: &str) -> Result<u64, Box<dyn Error>> {
-    // 'YYYY-MM-DD..YYYY-MM-DD'
-    let spec = " is:pr org:rust-lang is:merged merged:".to_owned() + week_spec;
-    let token = token()?;
-    let octocrab = Octocrab::builder().personal_token(token).build()?;
-    let search = octocrab
-        .search()
-        .issues_and_pull_requests(&spec)
-        .per_page(100);
-    let mut page = search.send().await?;
-    // get and write the total count
+async fn prs(source: &dyn PrSource, config: &Config, week: &Week) -> Result<u64, Box<dyn Error>> {
     let _ = fs::create_dir_all("cache"); // ignore possible errors
-    let total_count = page.total_count.unwrap_or(0);
+    let mut total_count = 0;
+    let mut seen = HashSet::new();
+    let mut prs = Vec::new();
+    let queries = config
+        .queries
+        .iter()
+        .map(|q| q.search(&week.query()))
+        .collect::<Vec<_>>();
+    let mut progress = cache::read_progress(&week.to_string(), &queries);
+    for query in &config.queries {
+        let mut ranges = vec![*week];
+        while let Some(range) = ranges.pop() {
+            let (total, found) =
+                fetch(source, config, &query.search(&range.query()), &mut progress).await?;
+            // GitHub won't give us more than 1000 results per search
+            if (found.len() as u64) < total {
+                if let Some(parts) = range.split() {
+                    println!("got {} of {total} PRs for {range}, splitting", found.len());
+                    ranges.extend(parts);
+                    continue;
+                }
+                println!(
+                    "warning: got only {} of {total} PRs for {range}",
+                    found.len()
+                );
+            }
+            total_count += total;
+            for pr in found {
+                // PRs found by more than one query only count once
+                if seen.insert(pr.url.clone()) {
+                    prs.push(pr);
+                } else {
+                    total_count = total_count.saturating_sub(1);
+                }
+            }
+        }
+    }
+    // make sure nothing that came in by rollup is missing
+    let rollups = prs
+        .iter()
+        .filter(|pr| pr.is_rollup())
+        .map(PullRequest::rollup_members)
+        .collect::<Vec<_>>();
+    for (org, repo, number) in rollups.into_iter().flatten() {
+        let url = format!("https://github.com/{org}/{repo}/pull/{number}");
+        if seen.contains(&url) {
+            continue;
+        }
+        match source::pull_retrying(source, &org, &repo, number, config.retries).await? {
+            // closed isn't enough, members can be closed without merging
+            Some(pull) if pull.merged => {
+                if let Some(pr) = PullRequest::from_issue(&pull.issue) {
+                    println!("added {url} from rollup");
+                    seen.insert(pr.url.clone());
+                    prs.push(pr);
+                    total_count += 1;
+                }
+            }
+            Some(_) => println!("warning: {url} from rollup isn't merged"),
+            None => println!("warning: couldn't get {url} from rollup"),
+        }
+    }
+    // write the total count
     {
         let mut out = fs::File::create("cache/num_prs")?;
         writeln!(
@@ -95,111 +197,175 @@ async fn prs(week_spec: &str) -> Result<u64, Box<dyn Error>> {
             total_count
         )?;
     }
-    // get all the PRs
-    let mut prs = page.take_items();
-    while let Some(mut new_page) = octocrab.get_page(&page.next).await? {
-        prs.extend(new_page.take_items());
-        page = new_page;
-    }
-    let repos: HashMap<&'static str, &'static str> = HashMap::from([
-        ("rust-clippy", "clippy"),
-        ("rustfmt", "rustfmt"),
-        ("cargo", "cargo"),
-        ("rustc_codegen_gcc", "codegen\\_gcc"),
-        ("futures-rs", "futures"),
-        ("rustup", "rustup"),
-        ("libc", "libc"),
-        ("docs.rs", "docs.rs"),
-        ("hashbrown", "hashbrown"),
-        ("miri", "miri"),
-        ("rust-analyzer", "rust-analyzer"),
-        ("rust-bindgen", "bindgen"),
-    ]);
+    cache::write_prs("cache/prs.json", &prs)?;
+    cache::clear_progress();
+    Ok(total_count)
+}
 
-    let out = fs::File::create("cache/prs")?;
-    let mut out = io::BufWriter::new(out);
-    for pr in prs {
-        let url = pr.html_url;
-        if let Some(unprefixed) = url.path().strip_prefix("/rust-lang/") {
-            if let Some(repo) = unprefixed.split_once("/") {
-                if let Some(reponame) = repos.get(repo.0) {
-                    if !pr.title.starts_with(reponame) {
-                        writeln!(
-                            out,
-                            "* [{}: {}]({})",
-                            reponame,
-                            pr.title.trim_matches(&[' ', '.'][..]),
-                            url
-                        )?;
-                        continue;
-                    }
-                }
-            }
+/// the title as it should appear in the list, prefixed with the repo if notable
+fn display_title(pr: &PullRequest, config: &Config) -> String {
+    let title = pr.title.trim_matches(&[' ', '.'][..]);
+    if let Some(prefix) = config.repo(&pr.org, &pr.repo).and_then(|r| r.prefix) {
+        if !title.starts_with(&prefix) {
+            return format!("{}: {title}", escape_markdown(&prefix));
         }
-        writeln!(
-            out,
-            "* [{}]({})",
-            pr.title.trim_matches(&[' ', '.'][..]),
-            url
-        )?;
     }
-    Ok(total_count)
+    title.to_owned()
 }
 
+/// the PRs of all earlier issues
 fn prev() -> Result<HashSet<String>, Box<dyn Error>> {
-    let prev = fs::File::open("cache/last_prs")?;
-    let prev = BufReader::new(prev);
-    let mut previous = HashSet::new();
-    for line in prev.lines() {
-        let line = line?;
-        previous.insert(if let Some((_, r)) = line.rsplit_once("](") {
-            r.to_owned()
-        } else {
-            line
-        });
+    let mut urls = History::read()?.urls();
+    // what we had before the history, possibly from before the switch to json
+    if Path::new("cache/last_prs.json").exists() {
+        let prs = cache::read_prs("cache/last_prs.json")?;
+        urls.extend(prs.into_iter().map(|pr| pr.url));
+    } else if Path::new("cache/last_prs").exists() {
+        urls.extend(cache::read_legacy_urls("cache/last_prs")?);
+    }
+    Ok(urls)
+}
+
+/// the section for PRs we can't put anywhere else
+static UNCATEGORISED: &str = "Uncategorised";
+
+/// the name of the section `pr` belongs to: the first with any of its labels,
+/// else that of its repo, else the first with a keyword in its title
+fn classify<'c>(pr: &PullRequest, config: &'c Config) -> Cow<'c, str> {
+    let sections = &config.sections;
+    if let Some(section) = sections
+        .iter()
+        .find(|s| s.labels.iter().any(|l| pr.labels.contains(l)))
+    {
+        return Cow::Borrowed(&section.name);
+    }
+    if let Some(section) = config.repo(&pr.org, &pr.repo).and_then(|r| r.section) {
+        return Cow::Owned(section);
     }
-    Ok(previous)
+    let title = pr.title.to_lowercase();
+    sections
+        .iter()
+        .find(|s| s.keywords.iter().any(|k| title.contains(&k.to_lowercase())))
+        .map_or(Cow::Borrowed(UNCATEGORISED), |s| Cow::Borrowed(&s.name))
+}
+
+/// a section name with its rendered PR entries
+type Sections = Vec<(String, Vec<String>)>;
+
+/// the PR list with a `####` heading per non-empty section
+fn render_sections(sections: &[(String, Vec<String>)]) -> String {
+    sections
+        .iter()
+        .map(|(name, prs)| format!("#### {name}\n\n{}\n", prs.join("\n")))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-fn filter(config: &Config) -> Result<Vec<String>, Box<dyn Error>> {
+/// filter the fetched PRs, returning the rendered entries grouped by section
+fn filter(config: &Config, dry_run: bool) -> Result<Sections, Box<dyn Error>> {
     let previous = prev()?;
-    let prs = fs::File::open("cache/prs")?;
+    let history = History::read()?;
+    let prs = cache::read_prs("cache/prs.json")?;
+    let rules = Rules::new(&config.rules)?;
+    let ignore_list = &config.ignore;
+    let order = config.order.iter().map(|s| &s[..]).collect::<Vec<_>>();
+    let title_options = config.title_options();
     let mut sorted_prs = Vec::new();
-    let ignore_list = get_list(config, "ignore")?;
-    let order = get_list(config, "order")?;
-    let code_words: HashSet<String> = get_list(config, "code_keywords")?
-        .into_iter()
-        .map(ToOwned::to_owned)
-        .collect();
-    for pr in BufReader::new(prs).lines() {
-        let pr = pr?;
-        let (title, href) = pr.rsplit_once("](").unwrap_or((&pr, ""));
-        let title = format_title(&code_words, title.strip_prefix("* [").unwrap_or(title));
-        if previous.contains(href) {
+    let mut flagged = Vec::new();
+    let mut dropped = Vec::new();
+    for pr in &prs {
+        if previous.contains(&pr.url) {
+            if dry_run {
+                println!("drop (previous issue): {}", pr.url);
+            }
+            continue;
+        }
+        if let Some((issue, reason)) = history.dropped(&pr.url) {
+            if dry_run {
+                println!(
+                    "drop (dropped from issue {}: {reason}): {}",
+                    issue.number, pr.url
+                );
+            }
+            // still dropped for the same reason, not by the editor
+            dropped.push(Dropped {
+                url: pr.url.clone(),
+                reason: reason.to_owned(),
+            });
             continue;
         }
-        let lower = pr.to_lowercase();
-        if !ignore_list.iter().any(|kw| lower.contains(kw)) {
-            sorted_prs.push(format!("* [{title}]({href}",));
+        let title = display_title(pr, config);
+        let lower = format!("* [{title}]({})", pr.url).to_lowercase();
+        let (action, reason) = if let Some((n, action)) = rules.verdict(pr) {
+            (action, format!("rule {n}"))
+        } else if pr.is_rollup() {
+            // its PRs are on the list themselves
+            (Action::Drop, "rollup".to_owned())
+        } else if config.repo(&pr.org, &pr.repo).is_some_and(|r| r.ignore) {
+            (Action::Drop, "ignored repo".to_owned())
+        } else if let Some(kw) = ignore_list.iter().find(|kw| lower.contains(&kw[..])) {
+            (Action::Drop, format!("ignore {kw:?}"))
+        } else {
+            (Action::Keep, "no rule".to_owned())
+        };
+        let section = classify(pr, config);
+        if dry_run {
+            println!("{action} ({reason}) [{section}]: {title} {}", pr.url);
         }
+        match action {
+            Action::Drop => {
+                dropped.push(Dropped {
+                    url: pr.url.clone(),
+                    reason,
+                });
+                continue;
+            }
+            Action::Flag => flagged.push((reason, pr.url.clone())),
+            Action::Keep => {}
+        }
+        sorted_prs.push((pr, section, format_title(&title, &title_options)));
     }
     let ord = &order[..];
-    sorted_prs.sort_by(|a, b| repo_title(a, ord).cmp(&repo_title(b, ord)));
-    let mut filtered_prs = fs::File::create("cache/filteredprs")?;
-    for pr in &sorted_prs {
-        writeln!(filtered_prs, "{}", pr)?;
+    sorted_prs.sort_by(|(a, _, a_title), (b, _, b_title)| {
+        repo_order(a, ord)
+            .cmp(&repo_order(b, ord))
+            .then_with(|| a_title.cmp(b_title))
+    });
+    // configured sections first, in order, then any only named by a repo
+    let mut sections = config
+        .sections
+        .iter()
+        .map(|s| (s.name.clone(), Vec::new()))
+        .collect::<Vec<_>>();
+    for (pr, section, title) in sorted_prs {
+        let entry = format!("* [{title}]({})", pr.url);
+        match sections.iter_mut().find(|(name, _)| *name == section) {
+            Some((_, prs)) => prs.push(entry),
+            None => sections.push((section.into_owned(), vec![entry])),
+        }
     }
-    Ok(sorted_prs)
+    // leave the rest for the editor to sort out at the end
+    if let Some(i) = sections.iter().position(|(name, _)| name == UNCATEGORISED) {
+        let uncategorised = sections.remove(i);
+        sections.push(uncategorised);
+    }
+    sections.retain(|(_, prs)| !prs.is_empty());
+    if dry_run {
+        return Ok(sections);
+    }
+    for (reason, url) in &flagged {
+        println!("please review ({reason}): {url}");
+    }
+    fs::write("cache/filteredprs", render_sections(&sections))?;
+    history::write_dropped(&dropped)?;
+    Ok(sections)
 }
 
-fn file_path() -> Result<PathBuf, Box<dyn Error>> {
-    std::fs::read_dir("../this-week-in-rust/draft")?
+fn file_path(config: &Config) -> Result<PathBuf, Box<dyn Error>> {
+    std::fs::read_dir(config.twir.join("draft"))?
         .filter_map(|e| e.ok())
         .map(|f| f.path())
-        .find(|p| {
-            p.extension()
-                .map_or(false, |e| e.eq_ignore_ascii_case("md"))
-        })
+        .find(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("md")))
         .ok_or_else(|| Cow::Borrowed("Draft not found").into())
 }
 
@@ -215,11 +381,44 @@ fn get_number(contents: &str) -> Result<&str, Box<dyn Error>> {
     }
 }
 
-fn command(binary: &str, args: &[&str], cwd: &str) -> Result<String, Box<dyn Error>> {
+/// add the issue in `draft` to the history, with the PRs we fetched for it
+/// but left out
+fn record_issue(number: u64, week: &Week, draft: &str) -> Result<(), Box<dyn Error>> {
+    let included = history::updates_links(draft);
+    let mut dropped = history::read_dropped();
+    let listed = included
+        .iter()
+        .chain(dropped.iter().map(|d| &d.url))
+        .cloned()
+        .collect::<HashSet<_>>();
+    let previous = prev()?;
+    for pr in cache::read_prs("cache/prs.json").unwrap_or_default() {
+        if !listed.contains(&pr.url) && !previous.contains(&pr.url) {
+            dropped.push(Dropped {
+                url: pr.url,
+                reason: "removed by the editor".into(),
+            });
+        }
+    }
+    let mut history = History::read()?;
+    history.record(history::Issue {
+        number,
+        week: Some(week.to_string()),
+        included,
+        dropped,
+    });
+    history.write()?;
+    // they are in the history now
+    let _ = fs::remove_file("cache/prs.json");
+    let _ = fs::remove_file(history::DROPPED);
+    Ok(())
+}
+
+fn command(binary: &str, args: &[&str], cwd: Option<&Path>) -> Result<String, Box<dyn Error>> {
     println!("Running {} {}", binary, args.join(" "));
     let mut cmd = Command::new(binary);
     cmd.args(args);
-    if cwd != "" {
+    if let Some(cwd) = cwd {
         cmd.current_dir(cwd);
     }
     let out = cmd.output()?;
@@ -230,30 +429,170 @@ fn command(binary: &str, args: &[&str], cwd: &str) -> Result<String, Box<dyn Err
     }
 }
 
+/// Runs git, the browser and the editor for `start` and `push`; with
+/// fixtures it only says what it would run, so those work in tests
+struct Runner {
+    dry_run: bool,
+}
+
+impl Runner {
+    fn new(config: &Config, fixtures: Option<&Path>) -> Self {
+        Runner {
+            dry_run: fixtures.or(config.fixtures.as_deref()).is_some(),
+        }
+    }
+
+    fn run(
+        &self,
+        binary: &str,
+        args: &[&str],
+        cwd: Option<&Path>,
+    ) -> Result<String, Box<dyn Error>> {
+        if self.dry_run {
+            println!("Not running {} {} with fixtures", binary, args.join(" "));
+            return Ok(String::new());
+        }
+        command(binary, args, cwd)
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    env::set_current_dir(WORKSPACE)?;
-    let cmd = env::args().nth(1);
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+    // resolve relative to where we were called, before changing directory
+    let fixtures = match args.iter().position(|a| a == "--fixtures") {
+        Some(i) if i + 1 < args.len() => {
+            let dir = args.remove(i + 1);
+            args.remove(i);
+            Some(env::current_dir()?.join(dir))
+        }
+        Some(_) => return Err("`--fixtures` needs a directory".into()),
+        None => None,
+    };
+    // tests run us in a scratch directory
+    env::set_current_dir(env::var_os("TWIRER_WORKSPACE").unwrap_or(WORKSPACE.into()))?;
+    let cmd = args.first();
     match cmd.as_ref().map_or("", |s| s) {
         "week" => {
-            let week_spec = week()?;
-            fs::write("cache/week_spec", &week_spec)?;
-            println!(
-                "https://github.com/search?q=is%3Apr+org%3Arust-lang+is%3Amerged+merged%3A{}",
-                week_spec
-            );
+            let conf = Config::read_or_default()?;
+            let cutoff = conf.cutoff()?;
+            let weeks = |arg: Option<&String>| match arg {
+                // an empty week would make an unreadable week spec
+                Some(n) => n.parse::<u8>().ok().filter(|&n| n > 0).ok_or_else(|| {
+                    format!("usage: twirer week [next|draft] [weeks], `{n}` is not 1 or more weeks")
+                }),
+                None => Ok(1),
+            };
+            let week = match args.get(1).map_or("next", |s| s) {
+                "show" => {
+                    show_week(&week::read(&cutoff)?, &conf, &cutoff);
+                    return Ok(());
+                }
+                "next" => week::read(&cutoff)?.next(weeks(args.get(2))?),
+                "prev" => week::read(&cutoff)?.prev(),
+                "set" => Week::parse(
+                    args.get(2)
+                        .ok_or("usage: twirer week set <from>..<until>")?,
+                    &cutoff,
+                )?,
+                "draft" => {
+                    let weeks = weeks(args.get(2))?;
+                    let date = Week::issue_date(&fs::read_to_string(file_path(&conf)?)?)?;
+                    let ends_on = week::parse_weekday(&conf.week_ends_on)?;
+                    Week::of_issue(date, ends_on, weeks, &cutoff)
+                }
+                _ => return Err(
+                    "usage: twirer week [show|next [weeks]|prev|set <from>..<until>|draft [weeks]]"
+                        .into(),
+                ),
+            };
+            week::write(&week)?;
+            show_week(&week, &conf, &cutoff);
         }
         "token" => {
-            println!("[{}]", token()?);
+            let conf = Config::read_or_default()?;
+            match args.get(1).map_or("", |s| s) {
+                "" => println!("using the token from {}", token::token(&conf)?.1),
+                "check" => check_token(&conf, fixtures.as_deref()).await?,
+                "store" => {
+                    token::store()?;
+                    println!("stored the token in the keyring");
+                }
+                _ => return Err("usage: twirer token [check|store]".into()),
+            }
         }
         "prs" => {
-            prs(&fs::read_to_string("cache/week_spec")?).await?;
+            // the config is optional here
+            let conf = Config::read_or_default()?;
+            let source = pr_source(&conf, fixtures.as_deref())?;
+            prs(&*source, &conf, &week::read(&conf.cutoff()?)?).await?;
         }
         "filter" => {
-            filter(&read_config()?)?;
+            let dry_run = args.get(1).is_some_and(|a| a == "--dry-run");
+            filter(&Config::read()?, dry_run)?;
         }
+        "title" => {
+            // `title [--explain] <title>`
+            let explain = args.get(1).is_some_and(|a| a == "--explain");
+            let title = args[if explain { 2 } else { 1 }..].join(" ");
+            let options = Config::read_or_default()?.title_options();
+            if explain {
+                for (word, rule) in explain_title(&title, &options) {
+                    match rule {
+                        Some(rule) => println!("{word}: {rule}"),
+                        None => println!("{word}: text"),
+                    }
+                }
+            }
+            println!("{}", format_title(&title, &options));
+        }
+        "history" => match args.get(1).map_or("", |s| s) {
+            "find" => {
+                let url = args.get(2).ok_or("usage: twirer history find <url>")?;
+                let history = History::read()?;
+                let found = history.find(url);
+                for found in &found {
+                    match found {
+                        Found::Included(issue) => println!("included in issue {}", issue.number),
+                        Found::Dropped(issue, reason) => {
+                            println!("dropped from issue {} ({reason})", issue.number)
+                        }
+                    }
+                }
+                if found.is_empty() {
+                    println!("not in any issue we know of");
+                    std::process::exit(1);
+                }
+            }
+            "list" => {
+                for issue in History::read()?.issues {
+                    println!(
+                        "{}: {} PRs, {} dropped{}",
+                        issue.number,
+                        issue.included.len(),
+                        issue.dropped.len(),
+                        issue.week.map_or(String::new(), |w| format!(" ({w})"))
+                    );
+                }
+            }
+            "import" => {
+                let mut history = History::read()?;
+                let dir = Config::read_or_default()?.twir.join("content");
+                let imported = history::import(&mut history, &dir)?;
+                history.write()?;
+                for path in &imported.skipped {
+                    println!("skipped {path}, it has no Rust updates");
+                }
+                println!(
+                    "imported {} issues, kept {} we already had",
+                    imported.issues, imported.known
+                );
+            }
+            _ => println!("usage: twirer history [list|find <url>|import]"),
+        },
         "branches" => {
-            let branches_out = command("git", &["branch"], TWIR)?;
+            let twir = Config::read_or_default()?.twir;
+            let branches_out = command("git", &["branch"], Some(&twir))?;
             let mut current = "";
             let branches = branches_out
                 .lines()
@@ -268,33 +607,53 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 .collect::<Vec<_>>();
             println!("{}\n* {}", branches.join(", "), current);
         }
+        "config" => match args.get(1).map_or("", |s| s) {
+            "check" => {
+                let conf = match Config::read() {
+                    Ok(conf) => conf,
+                    Err(e) => {
+                        println!("error: {e}");
+                        std::process::exit(1);
+                    }
+                };
+                let problems = conf.problems();
+                for problem in &problems {
+                    println!("warning: {problem}");
+                }
+                if problems.is_empty() {
+                    println!("config ok");
+                }
+            }
+            "migrate" => {
+                if Path::new(config::CONFIG).exists() {
+                    return Err(format!("{} already exists", config::CONFIG).into());
+                }
+                let conf = Config::from_legacy(&fs::read_to_string(config::LEGACY_CONFIG)?)?;
+                fs::write(config::CONFIG, conf.to_toml()?)?;
+                println!("wrote {}", config::CONFIG);
+            }
+            _ => println!("usage: twirer config [check|migrate]"),
+        },
         "editor" => {
-            let conf = read_config()?;
-            let editor = conf
-                .get("editor")
-                .ok_or(Cow::Borrowed("needs `editor=<path>` in config"))?;
-            command(editor, &[], "")?;
+            let conf = Config::read()?;
+            Runner::new(&conf, fixtures.as_deref()).run(conf.editor()?, &[], None)?;
         }
         "browser" => {
-            let conf = read_config()?;
-            let firefox = conf
-                .get("firefox")
-                .ok_or(Cow::Borrowed("needs `firefox=<path>` in config"))?;
-            //command(firefox, &[], "")?;
+            let conf = Config::read()?;
+            let firefox = conf.firefox()?;
+            //command(firefox, &[], None)?;
             println!("{firefox:?}");
         }
         "start" => {
             println!("start");
-            let conf = read_config()?;
-            let firefox = conf
-                .get("firefox")
-                .ok_or(Cow::Borrowed("needs `firefox=<path>` in config"))?;
-            let editor = conf
-                .get("editor")
-                .ok_or(Cow::Borrowed("needs `editor=<path>` in config"))?;
-            command("git", &["checkout", "master"], TWIR)?;
-            command("git", &["pull"], TWIR)?;
-            let file_path = file_path()?;
+            let conf = Config::read()?;
+            let firefox = conf.firefox()?;
+            let editor = conf.editor()?;
+            let runner = Runner::new(&conf, fixtures.as_deref());
+            let twir = Some(conf.twir.as_path());
+            runner.run("git", &["checkout", "master"], twir)?;
+            runner.run("git", &["pull"], twir)?;
+            let file_path = file_path(&conf)?;
             let contents = std::fs::read_to_string(&file_path)?;
             if !(contents.contains("<!-- COTW goes here -->")
                 && contents.contains("<!-- QOTW goes here -->")
@@ -303,7 +662,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 println!("error: setup not done yet. Try again later.");
                 return Ok(());
             }
-            command(
+            runner.run(
                 firefox,
                 &[
                     "--new-tab",
@@ -311,17 +670,19 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     "--new-tab",
                     "https://users.rust-lang.org/t/twir-quote-of-the-week/328/last",
                 ],
-                "",
+                None,
             )?;
             // insert the C/QotW templates & filtered PRs into the document
-            let week_spec = std::fs::read_to_string("cache/week_spec")?;
-            let num_prs = prs(&week_spec).await?;
+            let week = week::read(&conf.cutoff()?)?;
+            let source = pr_source(&conf, fixtures.as_deref())?;
+            let num_prs = prs(&*source, &conf, &week).await?;
             println!("found {} prs", num_prs);
-            let filtered_prs = filter(&conf)?.join("\n");
+            let filtered_prs = render_sections(&filter(&conf, false)?);
             println!("filtered prs");
+            let merged = merged_link(&conf, &week.query());
             let updates = format!(
                 "{num_prs} pull requests were [merged in the last week][merged]\n\
-\n[merged]: https://github.com/search?q=is%3Apr+org%3Arust-lang+is%3Amerged+merged%3A{week_spec}\n\
+\n[merged]: {merged}\n\
 \n{filtered_prs}"
             );
             let contents = contents
@@ -338,295 +699,158 @@ async fn main() -> Result<(), Box<dyn Error>> {
             std::fs::write(&file_path, contents)?;
             println!("updated contents, opening editor");
             // open the document with editor
-            command(editor, &[file_path.as_os_str().to_str().unwrap()], "")?;
+            runner.run(editor, &[file_path.as_os_str().to_str().unwrap()], None)?;
         }
         "check" => {
-            let file_path = file_path()?;
-            let contents = std::fs::read_to_string(&file_path)?;
-            // check markdown
-            let chapters = contents.split("\n##");
-            let mut err = 0;
-            for chapter in chapters {
-                let (title, text) = chapter.split_once("\n").unwrap();
-                match title.trim() {
-                    "Crate of the Week" | "Quote of the Week" => {
-                        check_markdown(chapter);
+            // `check [--format human|json] [--fix [--dry-run]]`
+            let (mut json, mut fix, mut dry_run) = (false, false, false);
+            let mut flags = args[1..].iter().map(|a| &a[..]);
+            while let Some(flag) = flags.next() {
+                match (flag, flags.clone().next()) {
+                    ("--format", Some("json")) => json = true,
+                    ("--format", Some("human")) => json = false,
+                    ("--fix", _) => fix = true,
+                    ("--dry-run", _) => dry_run = true,
+                    _ => {
+                        return Err(
+                            "usage: twirer check [--format human|json] [--fix [--dry-run]]".into(),
+                        )
                     }
-                    "Updates from the Rust Project" => {
-                        err += check_markdown(chapter);
-                        let mut parts = text.splitn(3, "\n\n");
-                        let num = parts.next().expect("missing Updates prs num");
-                        assert!(
-                            num.ends_with(" pull requests were [merged in the last week][merged]")
-                        );
-                        let link = parts.next().expect("missing Updates link");
-                        assert!(link.starts_with("[merged]: https://github.com/search?q=is%3Apr+org%3Arust-lang+is%3Amerged+merged%3A"));
-                        let prs = parts.next().expect("missing PRs");
-                        for pr in prs.lines() {
-                            if let Some(p) = pr.strip_prefix("* [") {
-                                if let Some(p) = p.strip_suffix(")") {
-                                    if let Some((title, link)) = p.split_once("](") {
-                                        err += check_title(title);
-                                        err += check_link(link)
-                                    } else {
-                                        println!("Wrong PR link: {}", pr);
-                                        err += 1;
-                                    }
-                                } else {
-                                    println!("Wrong PR link: {}", pr);
-                                    err += 1;
-                                }
-                            } else {
-                                println!("Wrong PR link: {}", pr);
-                                err += 1;
-                            }
-                        }
-                    }
-                    _ => {}
                 }
-                if err > 0 {
-                    if err == 1 {
-                        panic!("There was 1 error");
-                    }
-                    panic!("There were {} errors", err);
+                if flag == "--format" {
+                    flags.next();
+                }
+            }
+            if dry_run && !fix {
+                return Err("`--dry-run` only makes sense with `--fix`".into());
+            }
+            let conf = Config::read_or_default()?;
+            let keys = |urls: HashSet<String>| {
+                urls.iter()
+                    .filter_map(|u| check::pr_key(u))
+                    .collect::<HashSet<_>>()
+            };
+            let context = check::Context {
+                // everything up to the week spec, which comes last
+                merged: format!("[merged]: {}", merged_link(&conf, "")),
+                week: conf
+                    .cutoff()
+                    .and_then(|cutoff| week::read(&cutoff))
+                    .ok()
+                    .map(|week| {
+                        let link = format!("[merged]: {}", merged_link(&conf, &week.query()));
+                        (week.query(), link)
+                    }),
+                num_prs: fs::read_to_string("cache/num_prs")
+                    .ok()
+                    .and_then(|n| n.split(' ').next()?.parse().ok()),
+                prs: cache::read_prs("cache/prs.json")
+                    .ok()
+                    .map(|prs| keys(prs.into_iter().map(|pr| pr.url).collect())),
+                previous: keys(prev()?),
+                history: History::read()?,
+                orgs: conf.orgs(),
+            };
+            let file_path = file_path(&conf)?;
+            let file = file_path.display().to_string();
+            let contents = std::fs::read_to_string(&file_path)?;
+            let lines = contents.lines().collect::<Vec<_>>();
+            let mut checker = Checker::new(&file[..], &lines, &context);
+            checker.check_draft();
+            let mut diagnostics = checker.diagnostics;
+            let fixed;
+            let fixed_lines;
+            let lines = if fix {
+                fixed = check::apply_fixes(&lines, &diagnostics);
+                let diff = check::diff(&file, &lines, &fixed);
+                // keep the json clean
+                if json {
+                    eprint!("{diff}");
+                } else {
+                    print!("{diff}");
+                }
+                let mut text = fixed.join("\n");
+                if contents.ends_with('\n') {
+                    text.push('\n');
                 }
+                if !dry_run {
+                    fs::write(&file_path, &text)?;
+                }
+                // report what is left
+                fixed_lines = fixed.iter().map(|l| &l[..]).collect::<Vec<_>>();
+                let mut checker = Checker::new(&file[..], &fixed_lines, &context);
+                checker.check_draft();
+                diagnostics = checker.diagnostics;
+                &fixed_lines
+            } else {
+                &lines
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diagnostics)?);
+            } else {
+                check::print(&diagnostics, lines);
+            }
+            if !diagnostics.is_empty() {
+                std::process::exit(1);
             }
         }
         "push" => {
-            let conf = read_config()?;
-            let firefox = conf
-                .get("firefox")
-                .ok_or(Cow::Borrowed("needs `firefox=<path>` in config"))?;
-            let file_path = file_path()?;
+            let conf = Config::read()?;
+            let firefox = conf.firefox()?;
+            let runner = Runner::new(&conf, fixtures.as_deref());
+            let twir = Some(conf.twir.as_path());
+            let file_path = file_path(&conf)?;
             let contents = std::fs::read_to_string(&file_path)?;
-            let number = get_number(&contents)?;
+            let number = get_number(&contents)?.trim();
+            let number = number
+                .parse::<u64>()
+                .map_err(|e| format!("`{number}` is not an issue number: {e}"))?;
+            // remember what went into this issue and what didn't, before
+            // changing anything we can't take back
+            let published = week::read(&conf.cutoff()?)?;
+            record_issue(number, &published, &contents)?;
             // create, commit & push the new branch
-            command(
+            runner.run(
                 "git",
                 &["checkout", "-b", &format!("twir-{}", number)],
-                TWIR,
+                twir,
             )?;
-            command(
+            runner.run(
                 "git",
                 &[
                     "add",
                     &format!("draft/{}", file_path.file_name().unwrap().to_string_lossy()),
                 ],
-                TWIR,
+                twir,
             )?;
-            command("git", &["commit", "-m", "C/QotW and notable changes"], TWIR)?;
-            command("git", &["push", "llogiq"], TWIR)?;
+            runner.run("git", &["commit", "-m", "C/QotW and notable changes"], twir)?;
+            runner.run("git", &["push", &conf.remote], twir)?;
             // open the PR view
-            command(
+            runner.run(
                 firefox,
                 &[
                     "--new-tab",
-                    &format!(
-                        "https://github.com/llogiq/this-week-in-rust/pull/new/twir-{}",
-                        number
-                    ),
+                    &format!("https://github.com/{}/pull/new/twir-{}", conf.fork, number),
                 ],
-                "",
+                None,
             )?;
-            // update the week spec
-            let week_spec = week()?;
-            println!("set week to {}", week_spec);
-            fs::write("cache/week_spec", &week_spec)?;
-            // move cache/prs to cache/last_prs
-            std::fs::rename("cache/prs", "cache/last_prs")?;
+            // update the week spec, keeping the one we just published
+            fs::write(week::LAST_WEEK_SPEC, published.to_string())?;
+            let week = published.next(1);
+            println!("set week to {week}");
+            week::write(&week)?;
             // delete previous branch
-            if let Ok(num) = str::parse::<u64>(number) {
-                let previous_branch = format!("twir-{}", num - 1);
-                command("git", &["branch", "-d", &previous_branch], TWIR)?;
-                command(
-                    "git",
-                    &["push", "--delete", "llogiq", &previous_branch],
-                    TWIR,
-                )?;
-            }
+            let previous_branch = format!("twir-{}", number - 1);
+            runner.run("git", &["branch", "-d", &previous_branch], twir)?;
+            runner.run(
+                "git",
+                &["push", "--delete", &conf.remote, &previous_branch],
+                twir,
+            )?;
         }
         _ => {
-            println!("usage: twirer [prs <spec>|filter]");
+            println!("usage: twirer [--fixtures <dir>] [prs <spec>|filter [--dry-run]]");
         }
     }
     Ok(())
 }
-
-fn check_link(link: &str) -> usize {
-    if let Some(rest) = link.strip_prefix("https://github.com/rust-lang/") {
-        let mut parts = rest.splitn(3, '/');
-        let repo = parts.next().unwrap_or("?");
-        let pull = parts.next().unwrap_or("?");
-        let number = parts.next().unwrap_or("?");
-        if !repo
-            .chars()
-            .all(|c| ['_', '-', '.'].contains(&c) || c.is_ascii_alphanumeric())
-            || pull != "pull"
-            || str::parse::<u32>(number).is_err()
-        {
-            println!("wrong link: {}", link);
-            return 1;
-        }
-    }
-    0
-}
-
-fn check_title(title: &str) -> usize {
-    let mut in_code = false;
-    let mut escape = false;
-    for c in title.chars() {
-        if c == '`' {
-            in_code = !in_code;
-        } else if !in_code {
-            if !escape && ['<', '>', '[', ']', '_'].contains(&c) {
-                println!("unescaped `{}` in non-code title: {}", c, title);
-                return 1;
-            }
-            escape = c == '\\';
-        }
-    }
-    if escape {
-        println!("wonky backslash at the end: {}", title);
-        1
-    } else if in_code {
-        println!("unmatched backticks in {}", title);
-        1
-    } else {
-        0
-    }
-}
-
-fn has_unescaped(mut haystack: &str, needle: &str) -> bool {
-    while let Some(pos) = haystack.find(needle) {
-        if haystack.as_bytes()[pos.saturating_sub(1)] == b'\\' {
-            haystack = &haystack[pos + needle.len()..];
-        } else {
-            return true;
-        }
-    }
-    false
-}
-
-#[derive(Debug)]
-struct Word<'t> {
-    text: &'t str,
-    is_code: bool,
-    colon: bool,
-}
-
-impl<'t> Word<'t> {
-    fn new(s: &'t str, in_code: bool, code_words: &HashSet<String>) -> (Self, bool, bool) {
-        let (text, colon) = if let Some(t) = s.strip_suffix(':') {
-            (t, true)
-        } else {
-            (s, false)
-        };
-        if let Some(text) = text.strip_suffix("`]").and_then(|s| s.strip_prefix("[`")) {
-            return (Word { text, is_code: true, colon }, true, true)
-        }
-        let (text, out_code) = if let Some(t) = text.strip_suffix('`') {
-            (t, true)
-        } else {
-            (text, false)
-        };
-        let (text, colon) = if let Some(t) = text.strip_suffix(':') {
-            (t, true)
-        } else {
-            (text, colon)
-        };
-        let (text, into_code) = if let Some(t) = text.strip_prefix('`') {
-            (t, true)
-        } else {
-            (text, false)
-        };
-        let is_code = into_code ||
-            in_code
-            // snake case terms
-            || has_unescaped(text, "_")
-            // generics
-            || has_unescaped(text, "<")
-            // paths
-            || text.contains("::")
-            // attributes
-            || text.contains("#[")
-            // function calls, but not parenthesized texts
-            || text.contains("(") && text.ends_with(")") && !text.starts_with('(')
-            || code_words.contains(text);
-        (
-            Word {
-                text,
-                is_code,
-                colon,
-            },
-            into_code,
-            out_code,
-        )
-    }
-}
-
-fn format_title(code_words: &HashSet<String>, title: &str) -> String {
-    let mut in_code = false;
-    let mut words = Vec::new();
-    for text in title.trim().split_whitespace() {
-        let (word, into_code, out_code) = Word::new(text, in_code, code_words);
-        in_code = (in_code | into_code) & !out_code;
-        words.push(word);
-    }
-    let mut first = true;
-    let mut result = String::new();
-    for w in 0..words.len() {
-        let word = &words[w];
-        if word.is_code && w.checked_sub(1).map_or(true, |i| !words[i].is_code) {
-            result.push('`');
-            result.push_str(word.text);
-            first = false;
-        } else if first
-            && word.text.starts_with(char::is_uppercase)
-            && word.text.chars().any(char::is_lowercase)
-        {
-            // lowercase initial title case
-            let mut c = word.text.chars();
-            result.extend(c.next().unwrap().to_lowercase());
-            result.push_str(c.as_str());
-            first &= word.colon;
-        } else {
-            let text = word.text;
-            result.push_str(if text == "->" { "→" } else { text });
-            first &= word.colon;
-        }
-        // close any opened code span
-        if word.is_code && words.get(w + 1).map_or(true, |n| !n.is_code) {
-            result.push('`');
-        }
-        // add any given colon
-        if word.colon {
-            result.push(':');
-        }
-        // add whitespace between words
-        if w < words.len() - 1 {
-            result.push(' ');
-        }
-    }
-    result
-}
-
-fn check_markdown(chapter: &str) -> usize {
-    let mut err = 0;
-    for l in chapter.split('\n') {
-        // check for stray whitespace
-        if let 0 | 2 = l
-            .chars()
-            .rev()
-            .take(3)
-            .filter(|&c| c.is_whitespace())
-            .count()
-        {
-            // Ok
-        } else {
-            println!("line: `{}` ends with irregular whitespace", l);
-            err += 1;
-        }
-        // TODO: check link titles, uneven code spans, etc.
-    }
-    err
-}
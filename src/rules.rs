@@ -0,0 +1,177 @@
+use crate::cache::PullRequest;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+/// What a matching rule does with a PR
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Drop,
+    Keep,
+    /// keep, but tell the editor to have a look
+    Flag,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Action::Drop => "drop",
+            Action::Keep => "keep",
+            Action::Flag => "flag",
+        })
+    }
+}
+
+/// A filter rule from the config. It matches a PR if every given criterion
+/// does; a criterion with a list matches if any of its entries does.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub action: Action,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    /// either `org/repo` or just `repo`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repos: Vec<String>,
+    /// a regular expression on the unformatted title
+    pub title: Option<String>,
+}
+
+impl Rule {
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+            && self.authors.is_empty()
+            && self.repos.is_empty()
+            && self.title.is_none()
+    }
+}
+
+/// The configured rules, ready to be matched
+pub struct Rules<'c> {
+    rules: Vec<(&'c Rule, Option<Regex>)>,
+}
+
+impl<'c> Rules<'c> {
+    pub fn new(rules: &'c [Rule]) -> Result<Self, Box<dyn Error>> {
+        let mut compiled = Vec::with_capacity(rules.len());
+        for (i, rule) in rules.iter().enumerate() {
+            let title = match &rule.title {
+                Some(t) => Some(Regex::new(t).map_err(|e| format!("rule {}: {e}", i + 1))?),
+                None => None,
+            };
+            compiled.push((rule, title));
+        }
+        Ok(Rules { rules: compiled })
+    }
+
+    /// the (1-based) number and action of the first rule matching `pr`
+    pub fn verdict(&self, pr: &PullRequest) -> Option<(usize, Action)> {
+        self.rules
+            .iter()
+            .position(|(rule, title)| {
+                (rule.labels.is_empty() || rule.labels.iter().any(|l| pr.labels.contains(l)))
+                    && (rule.authors.is_empty()
+                        || rule
+                            .authors
+                            .iter()
+                            .any(|a| a.eq_ignore_ascii_case(&pr.author)))
+                    && (rule.repos.is_empty()
+                        || rule.repos.iter().any(|r| match r.split_once('/') {
+                            Some((org, repo)) => org == pr.org && repo == pr.repo,
+                            None => *r == pr.repo,
+                        }))
+                    && title.as_ref().is_none_or(|t| t.is_match(&pr.title))
+            })
+            .map(|i| (i + 1, self.rules[i].0.action))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pr(repo: &str, author: &str, labels: &[&str], title: &str) -> PullRequest {
+        PullRequest {
+            org: "rust-lang".into(),
+            repo: repo.into(),
+            number: 1,
+            title: title.into(),
+            author: author.into(),
+            labels: labels.iter().map(|&l| l.to_owned()).collect(),
+            merged_at: None,
+            url: format!("https://github.com/rust-lang/{repo}/pull/1"),
+            body: None,
+        }
+    }
+
+    fn rules(toml: &str) -> Vec<Rule> {
+        #[derive(Deserialize)]
+        struct Rules {
+            rules: Vec<Rule>,
+        }
+        toml::from_str::<Rules>(toml).unwrap().rules
+    }
+
+    #[test]
+    fn first_match_wins() {
+        let rules = rules(
+            r#"
+            [[rules]]
+            action = "keep"
+            authors = ["RalfJung"]
+            repos = ["miri"]
+
+            [[rules]]
+            action = "drop"
+            repos = ["rust-lang/miri", "other-org/rust"]
+
+            [[rules]]
+            action = "flag"
+            labels = ["T-lang", "relnotes"]
+            title = "^(?i)stabilize"
+
+            [[rules]]
+            action = "drop"
+            authors = ["dependabot[bot]"]
+            "#,
+        );
+        let rules = Rules::new(&rules).unwrap();
+        let verdict = |pr| rules.verdict(&pr);
+        assert_eq!(
+            verdict(pr("miri", "ralfjung", &[], "fix a bug")),
+            Some((1, Action::Keep))
+        );
+        assert_eq!(
+            verdict(pr("miri", "someone", &[], "fix a bug")),
+            Some((2, Action::Drop))
+        );
+        // `other-org/rust` doesn't match `rust-lang/rust`
+        assert_eq!(verdict(pr("rust", "someone", &[], "fix a bug")), None);
+        assert_eq!(
+            verdict(pr("rust", "someone", &["relnotes"], "Stabilize `foo`")),
+            Some((3, Action::Flag))
+        );
+        // every criterion has to match
+        assert_eq!(
+            verdict(pr("rust", "someone", &["relnotes"], "fix a bug")),
+            None
+        );
+        assert_eq!(verdict(pr("rust", "someone", &[], "stabilize `foo`")), None);
+        assert_eq!(
+            verdict(pr("cargo", "dependabot[bot]", &[], "Bump serde")),
+            Some((4, Action::Drop))
+        );
+    }
+
+    #[test]
+    fn bad_regex() {
+        let rules =
+            rules("[[rules]]\naction = \"drop\"\n\n[[rules]]\naction = \"drop\"\ntitle = \"(\"");
+        let error = Rules::new(&rules).err().unwrap().to_string();
+        assert!(error.starts_with("rule 2: regex parse error"), "{error}");
+    }
+}
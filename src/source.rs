@@ -0,0 +1,370 @@
+use async_trait::async_trait;
+use octocrab::models::issues::Issue;
+use octocrab::Octocrab;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// One page of search results
+pub struct SearchPage {
+    pub total_count: u64,
+    pub items: Vec<Issue>,
+    pub has_next: bool,
+}
+
+/// A single PR, as the issues API returns it
+pub struct Pull {
+    pub issue: Issue,
+    /// whether it got merged rather than just closed
+    pub merged: bool,
+}
+
+impl Pull {
+    /// read the issues API's answer, which tells us whether the PR got
+    /// merged in `pull_request.merged_at`
+    fn parse(json: &str) -> Result<Self, Box<dyn Error>> {
+        #[derive(serde::Deserialize)]
+        struct Link {
+            merged_at: Option<String>,
+        }
+        #[derive(serde::Deserialize)]
+        struct Merged {
+            pull_request: Option<Link>,
+        }
+        let merged: Merged = serde_json::from_str(json)?;
+        Ok(Pull {
+            issue: serde_json::from_str(json)?,
+            merged: merged.pull_request.is_some_and(|l| l.merged_at.is_some()),
+        })
+    }
+}
+
+/// What GitHub tells us about the token we use
+#[derive(serde::Deserialize)]
+pub struct TokenInfo {
+    pub login: String,
+    /// the OAuth scopes, `None` for fine-grained tokens, which have none
+    pub scopes: Option<Vec<String>>,
+    /// when the token expires, as GitHub formats it, `None` if it doesn't
+    pub expires: Option<String>,
+}
+
+/// Somewhere we can search merged PRs
+#[async_trait(?Send)]
+pub trait PrSource {
+    /// get the given (1-based) page of results for the search `query`
+    async fn search(&self, query: &str, page: u32) -> Result<SearchPage, Box<dyn Error>>;
+
+    /// get a single PR, `None` if there is no such thing
+    async fn pull(
+        &self,
+        org: &str,
+        repo: &str,
+        number: u64,
+    ) -> Result<Option<Pull>, Box<dyn Error>>;
+
+    /// find out who the token belongs to, what it may do and how long
+    async fn token_info(&self) -> Result<TokenInfo, Box<dyn Error>>;
+}
+
+/// The real thing: GitHub's search API
+pub struct GitHub {
+    octocrab: Octocrab,
+}
+
+impl GitHub {
+    pub fn new(token: String) -> Result<Self, Box<dyn Error>> {
+        Ok(GitHub {
+            octocrab: Octocrab::builder().personal_token(token).build()?,
+        })
+    }
+
+    /// get `path` from the API, `None` if it isn't there, with the body and
+    /// whether there is a next page, failing with a `Retry` if waiting may help
+    async fn get(
+        &self,
+        path: &str,
+        params: Option<&[(&str, String)]>,
+    ) -> Result<Option<(String, bool)>, Box<dyn Error>> {
+        let url = self.octocrab.absolute_url(path)?;
+        // only timeouts may go away by themselves, not DNS, TLS or the like
+        let response = match self.octocrab._get(url, params).await {
+            Ok(response) => response,
+            Err(octocrab::Error::Http { source, .. }) if source.is_timeout() => {
+                return Err(Box::new(Retry {
+                    reason: source.to_string(),
+                    after: None,
+                }))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let status = response.status();
+        let header = |name| {
+            let value = response.headers().get(name)?;
+            value.to_str().ok().map(str::to_owned)
+        };
+        let retry_after = header("retry-after").and_then(|s| s.parse().ok());
+        let reset = (header("x-ratelimit-remaining").as_deref() == Some("0"))
+            .then(|| header("x-ratelimit-reset")?.parse::<u64>().ok())
+            .flatten()
+            .map(|reset| {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                reset.saturating_sub(now.as_secs())
+            });
+        let has_next = header("link").is_some_and(|l| l.contains("rel=\"next\""));
+        let body = match response.text().await {
+            Ok(body) => body,
+            Err(e) if e.is_timeout() => {
+                return Err(Box::new(Retry {
+                    reason: e.to_string(),
+                    after: None,
+                }))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if status.is_server_error() {
+            return Err(Box::new(Retry {
+                reason: format!("GitHub answered {status}"),
+                after: None,
+            }));
+        }
+        let rate_limited = retry_after.is_some() || reset.is_some() || body.contains("rate limit");
+        if (status.as_u16() == 403 || status.as_u16() == 429) && rate_limited {
+            return Err(Box::new(Retry {
+                reason: "hit GitHub's rate limit".into(),
+                after: retry_after.or(reset).map(Duration::from_secs),
+            }));
+        }
+        if status.as_u16() == 404 {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(format!("GitHub answered {status}: {body}").into());
+        }
+        Ok(Some((body, has_next)))
+    }
+}
+
+/// A failure that may go away if we wait a bit
+#[derive(Debug)]
+pub struct Retry {
+    pub reason: String,
+    /// how long GitHub told us to wait, if it did
+    pub after: Option<Duration>,
+}
+
+impl fmt::Display for Retry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl Error for Retry {}
+
+#[async_trait(?Send)]
+impl PrSource for GitHub {
+    async fn search(&self, query: &str, page: u32) -> Result<SearchPage, Box<dyn Error>> {
+        let params = [
+            ("q", query.to_owned()),
+            ("per_page", "100".to_owned()),
+            ("page", page.to_string()),
+        ];
+        let (body, has_next) = self
+            .get("search/issues", Some(&params))
+            .await?
+            .ok_or("GitHub doesn't know the search API")?;
+        let response: Response = serde_json::from_str(&body)?;
+        Ok(SearchPage {
+            total_count: response.total_count,
+            items: response.items,
+            has_next,
+        })
+    }
+
+    async fn pull(
+        &self,
+        org: &str,
+        repo: &str,
+        number: u64,
+    ) -> Result<Option<Pull>, Box<dyn Error>> {
+        let path = format!("repos/{org}/{repo}/issues/{number}");
+        match self.get(&path, None).await? {
+            Some((body, _)) => Ok(Some(Pull::parse(&body)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn token_info(&self) -> Result<TokenInfo, Box<dyn Error>> {
+        let url = self.octocrab.absolute_url("user")?;
+        let response = self.octocrab._get(url, None::<&()>).await?;
+        let status = response.status();
+        let header = |name| {
+            let value = response.headers().get(name)?;
+            value.to_str().ok().map(str::to_owned)
+        };
+        let scopes = header("x-oauth-scopes").map(|scopes| {
+            scopes
+                .split(',')
+                .map(|s| s.trim().to_owned())
+                .filter(|s| !s.is_empty())
+                .collect()
+        });
+        let expires = header("github-authentication-token-expiration");
+        let body = response.text().await?;
+        if status.as_u16() == 401 {
+            return Err("GitHub doesn't accept the token, it may be revoked or expired".into());
+        }
+        if !status.is_success() {
+            return Err(format!("GitHub answered {status}: {body}").into());
+        }
+        #[derive(serde::Deserialize)]
+        struct User {
+            login: String,
+        }
+        let user: User = serde_json::from_str(&body)?;
+        Ok(TokenInfo {
+            login: user.login,
+            scopes,
+            expires,
+        })
+    }
+}
+
+/// a bit of randomness so we don't retry in lockstep with everyone else
+fn jitter() -> Duration {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    Duration::from_millis(u64::from(nanos % 1000))
+}
+
+/// run `request`, retrying up to `retries` times with exponential backoff
+/// on failures that may be temporary
+async fn retrying<T, F>(retries: u32, mut request: impl FnMut() -> F) -> Result<T, Box<dyn Error>>
+where
+    F: Future<Output = Result<T, Box<dyn Error>>>,
+{
+    let mut attempt = 0;
+    loop {
+        let err = match request().await {
+            Ok(result) => return Ok(result),
+            Err(err) => err,
+        };
+        let retry = match err.downcast_ref::<Retry>() {
+            Some(retry) if attempt < retries => retry,
+            _ => return Err(err),
+        };
+        attempt += 1;
+        let backoff = Duration::from_secs(1 << attempt.min(6));
+        let wait = retry.after.unwrap_or(backoff) + jitter();
+        println!(
+            "\n{retry}, retrying in {}s ({attempt}/{retries})",
+            wait.as_secs()
+        );
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// search, retrying on failures that may be temporary
+pub async fn search_retrying(
+    source: &dyn PrSource,
+    query: &str,
+    page: u32,
+    retries: u32,
+) -> Result<SearchPage, Box<dyn Error>> {
+    retrying(retries, || source.search(query, page)).await
+}
+
+/// get a single PR, retrying on failures that may be temporary
+pub async fn pull_retrying(
+    source: &dyn PrSource,
+    org: &str,
+    repo: &str,
+    number: u64,
+    retries: u32,
+) -> Result<Option<Pull>, Box<dyn Error>> {
+    retrying(retries, || source.pull(org, repo, number)).await
+}
+
+/// the query as it goes into a search URL, which is also safe as a file name
+pub fn encode_query(query: &str) -> String {
+    let mut encoded = String::new();
+    for b in query.bytes() {
+        match b {
+            b' ' => encoded.push('+'),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(char::from(b))
+            }
+            _ => encoded.push_str(&format!("%{b:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Recorded search responses, as `<dir>/<encoded query>/<page>.json` files in
+/// the format the GitHub search API returns them, single PRs as
+/// `<dir>/<org>/<repo>/<number>.json` and the token info as `<dir>/token.json`
+pub struct Fixtures {
+    dir: PathBuf,
+}
+
+impl Fixtures {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Fixtures { dir: dir.into() }
+    }
+}
+
+/// the body of a search response
+#[derive(serde::Deserialize)]
+struct Response {
+    total_count: u64,
+    items: Vec<Issue>,
+}
+
+#[async_trait(?Send)]
+impl PrSource for Fixtures {
+    async fn search(&self, query: &str, page: u32) -> Result<SearchPage, Box<dyn Error>> {
+        let dir = self.dir.join(encode_query(query));
+        let path = dir.join(format!("{page}.json"));
+        let response = fs::read_to_string(&path).map_err(|e| {
+            format!(
+                "no recorded response for `{query}`: {}: {e}",
+                path.display()
+            )
+        })?;
+        let response: Response =
+            serde_json::from_str(&response).map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(SearchPage {
+            total_count: response.total_count,
+            items: response.items,
+            has_next: dir.join(format!("{}.json", page + 1)).exists(),
+        })
+    }
+
+    async fn pull(
+        &self,
+        org: &str,
+        repo: &str,
+        number: u64,
+    ) -> Result<Option<Pull>, Box<dyn Error>> {
+        let path = self.dir.join(org).join(repo).join(format!("{number}.json"));
+        if !path.exists() {
+            return Ok(None);
+        }
+        let pull = Pull::parse(&fs::read_to_string(&path)?);
+        Ok(Some(pull.map_err(|e| format!("{}: {e}", path.display()))?))
+    }
+
+    async fn token_info(&self) -> Result<TokenInfo, Box<dyn Error>> {
+        let path = self.dir.join("token.json");
+        Ok(serde_json::from_str(
+            &fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?,
+        )?)
+    }
+}
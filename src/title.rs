@@ -0,0 +1,755 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+/// How to format titles
+#[derive(Debug, Clone, Default)]
+pub struct TitleOptions {
+    /// words that are always code
+    pub code_keywords: HashSet<String>,
+    /// code detection rules we don't want applied
+    pub disabled_rules: HashSet<CodeRule>,
+    /// the `prefix: ` we put before titles of some repos, which is never code
+    pub prefixes: HashSet<String>,
+}
+
+/// The reasons for a word to be code, in the order we check them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeRule {
+    /// configured in `code_keywords`
+    Keyword,
+    /// `#[attr]`
+    Attribute,
+    /// `cfg(..)`, `cfg!(..)`, `cfg_attr(..)`
+    Cfg,
+    /// `clippy::lint_name`, `rustdoc::lint_name`
+    Lint,
+    /// `foo::bar`
+    Path,
+    /// `.method()`
+    Method,
+    /// `foo!`, `vec![..]`
+    Macro,
+    /// `foo(..)`, but not parenthesized text
+    Call,
+    /// `aarch64-apple-darwin`
+    TargetTriple,
+    /// `library/core/src`, `Cargo.toml`
+    FilePath,
+    /// `--flag`
+    LongFlag,
+    /// `-Zunstable-options`, `-Copt-level`
+    CompilerFlag,
+    /// `Vec<T>`
+    Generics,
+    /// `snake_case`
+    SnakeCase,
+    /// `CamelCase` type names
+    CamelCase,
+}
+
+impl CodeRule {
+    pub const ALL: &'static [CodeRule] = &[
+        CodeRule::Keyword,
+        CodeRule::Attribute,
+        CodeRule::Cfg,
+        CodeRule::Lint,
+        CodeRule::Path,
+        CodeRule::Method,
+        CodeRule::Macro,
+        CodeRule::Call,
+        CodeRule::TargetTriple,
+        CodeRule::FilePath,
+        CodeRule::LongFlag,
+        CodeRule::CompilerFlag,
+        CodeRule::Generics,
+        CodeRule::SnakeCase,
+        CodeRule::CamelCase,
+    ];
+
+    fn matches(self, text: &str, options: &TitleOptions) -> bool {
+        match self {
+            CodeRule::Keyword => options.code_keywords.contains(text),
+            CodeRule::Attribute => text.contains("#["),
+            CodeRule::Cfg => {
+                ["cfg(", "cfg!(", "cfg_attr("]
+                    .iter()
+                    .any(|p| text.starts_with(p))
+                    && text.ends_with(')')
+            }
+            CodeRule::Lint => ["clippy::", "rustdoc::", "rustc::"]
+                .iter()
+                .any(|p| text.starts_with(p)),
+            CodeRule::Path => text.contains("::"),
+            CodeRule::Method => text.strip_prefix('.').is_some_and(|m| {
+                let name = m.strip_suffix("()").unwrap_or(m);
+                is_ident(name) || m.contains('(') && m.ends_with(')')
+            }),
+            CodeRule::Macro => text.split_once('!').is_some_and(|(name, args)| {
+                is_ident(name)
+                    && name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+                    // `it!` is more likely an exclamation than a macro
+                    && (args.is_empty() && MACROS.contains(&name)
+                        || [("(", ")"), ("[", "]"), ("{", "}")]
+                            .iter()
+                            .any(|(o, c)| args.starts_with(o) && args.ends_with(c)))
+            }),
+            // function calls, but not parenthesized texts
+            CodeRule::Call => text.contains('(') && text.ends_with(')') && !text.starts_with('('),
+            CodeRule::TargetTriple => is_target_triple(text),
+            CodeRule::FilePath => is_file_path(text),
+            CodeRule::LongFlag => text
+                .strip_prefix("--")
+                .is_some_and(|f| f.starts_with(|c: char| c.is_ascii_alphanumeric())),
+            CodeRule::CompilerFlag => ["-Z", "-C"].iter().any(|p| {
+                text.strip_prefix(p)
+                    .is_some_and(|f| f.starts_with(|c: char| c.is_ascii_lowercase()))
+            }),
+            CodeRule::Generics => has_unescaped(text, "<"),
+            CodeRule::SnakeCase => has_unescaped(text, "_"),
+            CodeRule::CamelCase => is_camel_case(text),
+        }
+    }
+}
+
+impl fmt::Display for CodeRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the same names as in the config
+        f.write_str(match self {
+            CodeRule::Keyword => "keyword",
+            CodeRule::Attribute => "attribute",
+            CodeRule::Cfg => "cfg",
+            CodeRule::Lint => "lint",
+            CodeRule::Path => "path",
+            CodeRule::Method => "method",
+            CodeRule::Macro => "macro",
+            CodeRule::Call => "call",
+            CodeRule::TargetTriple => "target_triple",
+            CodeRule::FilePath => "file_path",
+            CodeRule::LongFlag => "long_flag",
+            CodeRule::CompilerFlag => "compiler_flag",
+            CodeRule::Generics => "generics",
+            CodeRule::SnakeCase => "snake_case",
+            CodeRule::CamelCase => "camel_case",
+        })
+    }
+}
+
+impl std::str::FromStr for CodeRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CodeRule::ALL
+            .iter()
+            .copied()
+            .find(|r| r.to_string() == s)
+            .ok_or_else(|| format!("unknown code rule `{s}`"))
+    }
+}
+
+fn is_ident(text: &str) -> bool {
+    !text.is_empty()
+        && !text.starts_with(|c: char| c.is_ascii_digit())
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// the macros that get mentioned without arguments
+const MACROS: &[&str] = &[
+    "println",
+    "print",
+    "eprintln",
+    "eprint",
+    "format",
+    "format_args",
+    "write",
+    "writeln",
+    "vec",
+    "panic",
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "assert_matches",
+    "debug_assert",
+    "debug_assert_eq",
+    "debug_assert_ne",
+    "unreachable",
+    "todo",
+    "unimplemented",
+    "matches",
+    "dbg",
+    "include",
+    "include_str",
+    "include_bytes",
+    "concat",
+    "stringify",
+    "env",
+    "option_env",
+    "cfg",
+    "line",
+    "file",
+    "column",
+    "module_path",
+    "compile_error",
+    "thread_local",
+    "macro_rules",
+    "asm",
+    "global_asm",
+    "naked_asm",
+    "offset_of",
+    "addr_of",
+    "addr_of_mut",
+    "ready",
+    "pin",
+    "try",
+];
+
+/// the architectures targets start with
+const ARCHES: &[&str] = &[
+    "x86_64",
+    "i386",
+    "i586",
+    "i686",
+    "aarch64",
+    "arm",
+    "thumb",
+    "riscv",
+    "wasm32",
+    "wasm64",
+    "powerpc",
+    "mips",
+    "s390x",
+    "sparc",
+    "loongarch64",
+    "nvptx64",
+    "avr",
+    "msp430",
+    "hexagon",
+    "csky",
+    "xtensa",
+    "bpf",
+];
+
+/// the vendors and systems of targets, one of which every triple names, so
+/// `arm-specific` is no target
+const SYSTEMS: &[&str] = &[
+    "unknown",
+    "pc",
+    "apple",
+    "sun",
+    "nvidia",
+    "fortanix",
+    "wrs",
+    "uwp",
+    "sony",
+    "nintendo",
+    "kmc",
+    "ibm",
+    "espressif",
+    "risc0",
+    "win7",
+    "linux",
+    "windows",
+    "darwin",
+    "ios",
+    "tvos",
+    "watchos",
+    "visionos",
+    "android",
+    "freebsd",
+    "netbsd",
+    "openbsd",
+    "dragonfly",
+    "illumos",
+    "solaris",
+    "fuchsia",
+    "redox",
+    "haiku",
+    "hermit",
+    "hurd",
+    "aix",
+    "l4re",
+    "nto",
+    "vxworks",
+    "uefi",
+    "none",
+    "wasi",
+    "wasip1",
+    "wasip2",
+    "emscripten",
+    "cuda",
+    "amdhsa",
+    "espidf",
+    "horizon",
+    "psp",
+    "cygwin",
+    "teeos",
+    "trusty",
+    "zkvm",
+];
+
+fn is_target_triple(text: &str) -> bool {
+    let parts = text.split('-').collect::<Vec<_>>();
+    (2..=4).contains(&parts.len())
+        && ARCHES.iter().any(|a| parts[0].starts_with(a))
+        && parts[1..].iter().any(|p| SYSTEMS.contains(p))
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+}
+
+/// the directories of the rust repo paths usually start with
+const ROOTS: &[&str] = &["library/", "compiler/", "src/", "tests/"];
+/// the extensions of files that get mentioned in titles
+const EXTENSIONS: &[&str] = &[
+    ".rs", ".toml", ".md", ".lock", ".json", ".yml", ".sh", ".py",
+];
+
+/// sites that look like file names
+const DOMAINS: &[&str] = &[
+    "docs.rs",
+    "rustup.rs",
+    "crates.io",
+    "rust-lang.org",
+    "github.com",
+];
+
+fn is_file_path(text: &str) -> bool {
+    let valid = |s: &str| {
+        s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || ['_', '-', '.'].contains(&c))
+    };
+    // plain words like `read/write/execute` are no path
+    let wordy = |s: &&str| s.chars().all(|c| c.is_ascii_alphabetic());
+    let segments = text.trim_end_matches('/').split('/').collect::<Vec<_>>();
+    let domain = DOMAINS.iter().any(|d| {
+        text.strip_suffix(d)
+            .is_some_and(|s| s.is_empty() || s.ends_with('.'))
+    });
+    !text.contains("//")
+        && !domain
+        && segments.iter().all(|s| !s.is_empty() && valid(s))
+        && (segments.len() >= 3 && !segments.iter().all(wordy)
+            || ROOTS.iter().any(|r| text.starts_with(r))
+            || EXTENSIONS
+                .iter()
+                .any(|e| text.ends_with(e) && text.len() > e.len()))
+}
+
+/// names that are camel case, but not code
+const PROPER_NOUNS: &[&str] = &[
+    "GitHub",
+    "GitLab",
+    "FreeBSD",
+    "NetBSD",
+    "OpenBSD",
+    "DragonFly",
+    "WebAssembly",
+    "PowerPC",
+    "LoongArch",
+    "MinGW",
+    "OpenSSL",
+    "OpenHarmony",
+    "HarmonyOS",
+    "VxWorks",
+    "JavaScript",
+    "TypeScript",
+    "PlayStation",
+    "YouTube",
+    "VSCode",
+    "IntelliJ",
+    "RustRover",
+];
+
+/// an upper case letter after a lower case one, like `HashMap`, but not `Rust`
+fn is_camel_case(text: &str) -> bool {
+    !PROPER_NOUNS.contains(&text)
+        && text.starts_with(|c: char| c.is_ascii_uppercase())
+        && text.chars().all(|c| c.is_ascii_alphanumeric())
+        && text
+            .as_bytes()
+            .windows(2)
+            .any(|w| w[0].is_ascii_lowercase() && w[1].is_ascii_uppercase())
+}
+
+/// A piece of a title
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token<'t> {
+    /// a code span that was already there, with the backticks it used
+    Code {
+        text: &'t str,
+        fence: &'t str,
+    },
+    /// a word, which may still turn out to be code
+    Word(&'t str),
+    /// punctuation around words, like quotes, parentheses or a colon
+    Punct(char),
+    Space,
+}
+
+/// the characters we take off the start of a word
+const LEADING: &[char] = &['(', '[', '"', '\'', '“', '‘'];
+/// the characters we take off the end of a word
+const TRAILING: &[char] = &['.', ',', ';', ':', '?', '"', '\'', '”', '’', ')', ']'];
+
+/// split a title into code spans, words, punctuation and spaces
+pub fn tokenize(title: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = title.trim();
+    while !rest.is_empty() {
+        let Some(start) = rest.find('`') else {
+            tokenize_text(rest, &mut tokens);
+            break;
+        };
+        tokenize_text(&rest[..start], &mut tokens);
+        let after = &rest[start..];
+        let fence_len = after.len() - after.trim_start_matches('`').len();
+        let (fence, after) = after.split_at(fence_len);
+        match find_fence(after, fence) {
+            Some(end) => {
+                tokens.push(Token::Code {
+                    text: &after[..end],
+                    fence,
+                });
+                rest = &after[end + fence.len()..];
+            }
+            // a stray backtick, drop it to keep the spans balanced
+            None => rest = after,
+        }
+    }
+    if tokens.last() == Some(&Token::Space) {
+        tokens.pop();
+    }
+    // `[`foo`]` is an intra-doc link, which is just code to us
+    let mut i = 1;
+    while i + 1 < tokens.len() {
+        if let [Token::Punct('['), Token::Code { .. }, Token::Punct(']')] = tokens[i - 1..=i + 1] {
+            tokens.remove(i + 1);
+            tokens.remove(i - 1);
+        }
+        i += 1;
+    }
+    tokens
+}
+
+/// the position of the closing `fence`, which must not be part of a longer one
+fn find_fence(text: &str, fence: &str) -> Option<usize> {
+    let mut from = 0;
+    while let Some(pos) = text[from..].find(fence) {
+        let pos = from + pos;
+        let end = pos + fence.len();
+        let run = text[end..].len() - text[end..].trim_start_matches('`').len();
+        if run == 0 {
+            return Some(pos);
+        }
+        from = end + run;
+    }
+    None
+}
+
+fn tokenize_text<'t>(mut text: &'t str, tokens: &mut Vec<Token<'t>>) {
+    loop {
+        let trimmed = text.trim_start();
+        // no double spaces where we dropped a stray backtick
+        if trimmed.len() < text.len() && tokens.last().is_some_and(|t| *t != Token::Space) {
+            tokens.push(Token::Space);
+        }
+        text = trimmed;
+        if text.is_empty() {
+            return;
+        }
+        let end = word_end(text);
+        split_punct(&text[..end], tokens);
+        text = &text[end..];
+    }
+}
+
+/// where the word at the start of `text` ends: at the next whitespace, unless
+/// that is inside brackets opened right after a word character, e.g.
+/// `HashMap<K, V>` or `map_or(false, ..)`, which are closed later on
+fn word_end(text: &str) -> usize {
+    let mut open = Vec::new();
+    let mut prev = None;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' | '(' | '[' if prev.is_some_and(|p: char| p.is_alphanumeric() || p == '_') => {
+                open.push(c)
+            }
+            '<' | '(' | '[' if !open.is_empty() => open.push(c),
+            '>' if prev == Some('-') => {}
+            '>' | ')' | ']' if open.last() == Some(&opener(c)) => {
+                open.pop();
+            }
+            c if c.is_whitespace() && (open.is_empty() || !closes(&text[i..], open.clone())) => {
+                return i;
+            }
+            _ => {}
+        }
+        prev = Some(c);
+    }
+    text.len()
+}
+
+fn opener(closer: char) -> char {
+    match closer {
+        '>' => '<',
+        ')' => '(',
+        _ => '[',
+    }
+}
+
+/// whether `text` closes all the `open` brackets
+fn closes(text: &str, mut open: Vec<char>) -> bool {
+    let mut prev = None;
+    for c in text.chars() {
+        match c {
+            '<' | '(' | '[' => open.push(c),
+            '>' if prev == Some('-') => {}
+            '>' | ')' | ']' => {
+                if open.pop() != Some(opener(c)) {
+                    return false;
+                }
+                if open.is_empty() {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+        prev = Some(c);
+    }
+    false
+}
+
+/// whether `word` has more closing `closer`s than opening ones
+fn unmatched(word: &str, closer: char) -> bool {
+    let open = opener(closer);
+    let mut depth = 0i32;
+    for c in word.chars() {
+        if c == open {
+            depth += 1;
+        } else if c == closer {
+            depth -= 1;
+        }
+    }
+    depth < 0
+}
+
+/// push the word with the punctuation around it
+fn split_punct<'t>(mut word: &'t str, tokens: &mut Vec<Token<'t>>) {
+    let mut trailing = Vec::new();
+    loop {
+        if let Some(c) = word.chars().next().filter(|c| LEADING.contains(c)) {
+            let rest = &word[c.len_utf8()..];
+            // keep brackets that belong to the word, as in `[T]::len`
+            let belongs = matches!(c, '(' | '[')
+                && rest
+                    .find(if c == '(' { ')' } else { ']' })
+                    .is_some_and(|end| end + 1 < rest.len() && !rest.ends_with(TRAILING));
+            if !belongs {
+                tokens.push(Token::Punct(c));
+                word = rest;
+                continue;
+            }
+        }
+        if let Some(c) = word.chars().next_back().filter(|c| TRAILING.contains(c)) {
+            let rest = &word[..word.len() - c.len_utf8()];
+            if !matches!(c, ')' | ']') || unmatched(word, c) || rest.is_empty() {
+                trailing.push(Token::Punct(c));
+                word = rest;
+                continue;
+            }
+        }
+        break;
+    }
+    if !word.is_empty() {
+        tokens.push(Token::Word(word));
+    }
+    tokens.extend(trailing.into_iter().rev());
+}
+
+fn has_unescaped(mut haystack: &str, needle: &str) -> bool {
+    while let Some(pos) = haystack.find(needle) {
+        if haystack.as_bytes()[pos.saturating_sub(1)] == b'\\' {
+            haystack = &haystack[pos + needle.len()..];
+        } else {
+            return true;
+        }
+    }
+    false
+}
+
+/// the first enabled rule that makes `text` code, if any
+pub fn code_rule(text: &str, options: &TitleOptions) -> Option<CodeRule> {
+    CodeRule::ALL
+        .iter()
+        .copied()
+        .filter(|r| !options.disabled_rules.contains(r))
+        .find(|r| r.matches(text, options))
+}
+
+/// whether the word at `i` is a configured `prefix:`
+fn is_prefix(tokens: &[Token<'_>], i: usize, options: &TitleOptions) -> bool {
+    match tokens[i..] {
+        [Token::Word(w), Token::Punct(':'), ..] if i == 0 => {
+            // the prefix may already be escaped
+            options.prefixes.contains(&w.replace('\\', ""))
+        }
+        _ => false,
+    }
+}
+
+/// the words of `title` with the rule that made them code, if any
+pub fn explain_title<'t>(
+    title: &'t str,
+    options: &TitleOptions,
+) -> Vec<(&'t str, Option<CodeRule>)> {
+    let tokens = tokenize(title);
+    tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(i, token)| match *token {
+            Token::Word(w) if is_prefix(&tokens, i, options) => Some((w, None)),
+            Token::Word(w) => Some((w, code_rule(w, options))),
+            _ => None,
+        })
+        .collect()
+}
+
+/// the characters markdown would interpret in title text
+const SPECIAL: &[char] = &['_', '*', '[', ']', '<', '>', '|'];
+
+/// push non-code `text`, escaping what markdown would interpret unless the
+/// title already did
+fn push_escaped(result: &mut String, text: &str) {
+    for c in text.chars() {
+        let escaped = ends_escaping(result);
+        if !escaped && (SPECIAL.contains(&c) || c == '#' && result.is_empty()) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+}
+
+/// escape what markdown would interpret in `text`, leaving what is already
+/// escaped alone
+pub fn escape_markdown(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    push_escaped(&mut result, text);
+    result
+}
+
+/// whether `text` ends in a backslash that escapes what comes next
+fn ends_escaping(text: &str) -> bool {
+    (text.len() - text.trim_end_matches('\\').len()) % 2 == 1
+}
+
+/// make sure a code span we start is one: a backslash before it would escape
+/// its backtick, and code right after code would run the fences together
+fn before_code(result: &mut String) {
+    if ends_escaping(result) {
+        result.push('\\');
+    } else if result.ends_with('`') {
+        result.push(' ');
+    }
+}
+
+/// A token ready to be written out
+enum Piece<'t> {
+    Code {
+        text: &'t str,
+        fence: &'t str,
+    },
+    /// detected code, which is merged with adjacent detected code
+    Detected(&'t str),
+    /// a configured prefix, which we leave as it is
+    Prefix(&'t str),
+    Text(&'t str),
+    Punct(char),
+    Space,
+}
+
+/// lowercase the title case parts of `word`, so `Rustdoc-Json` becomes
+/// `rustdoc-json` while `LLVM-Based` keeps its acronym
+fn lowercase_title_case(word: &str) -> String {
+    let parts = word.split('-').map(|part| {
+        let mut c = part.chars();
+        let title_case = c.next().is_some_and(char::is_uppercase)
+            && c.clone().any(char::is_lowercase)
+            && !c.any(char::is_uppercase);
+        if title_case {
+            part.to_lowercase()
+        } else {
+            part.to_owned()
+        }
+    });
+    parts.collect::<Vec<_>>().join("-")
+}
+
+/// format a PR title for the list: put code in backticks, escape markdown in
+/// the rest and lowercase an initial title case word
+pub fn format_title(title: &str, options: &TitleOptions) -> String {
+    let tokens = tokenize(title);
+    let pieces = tokens
+        .iter()
+        .enumerate()
+        .map(|(i, token)| match *token {
+            Token::Code { text, fence } => Piece::Code { text, fence },
+            Token::Word(w) if is_prefix(&tokens, i, options) => Piece::Prefix(w),
+            Token::Word(w) if code_rule(w, options).is_some() => Piece::Detected(w),
+            Token::Word(w) => Piece::Text(w),
+            Token::Punct(c) => Piece::Punct(c),
+            Token::Space => Piece::Space,
+        })
+        .collect::<Vec<_>>();
+    let mut first = true;
+    let mut result = String::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        let detected_at = |j: Option<usize>| {
+            j.and_then(|j| pieces.get(j))
+                .is_some_and(|p| matches!(p, Piece::Detected(_)))
+        };
+        match piece {
+            Piece::Code { text, fence } => {
+                // pad code that starts or ends with a backtick
+                let pad = if text.starts_with('`') || text.ends_with('`') {
+                    " "
+                } else {
+                    ""
+                };
+                before_code(&mut result);
+                result.push_str(&format!("{fence}{pad}{text}{pad}{fence}"));
+                first = false;
+            }
+            Piece::Detected(text) => {
+                // a space between detected code stays inside the span
+                if !(i >= 2 && matches!(pieces[i - 1], Piece::Space) && detected_at(Some(i - 2))) {
+                    before_code(&mut result);
+                    result.push('`');
+                }
+                result.push_str(text);
+                if !(matches!(pieces.get(i + 1), Some(Piece::Space)) && detected_at(Some(i + 2))) {
+                    result.push('`');
+                }
+                first = false;
+            }
+            Piece::Prefix(text) => push_escaped(&mut result, text),
+            Piece::Text(text) => {
+                if first {
+                    push_escaped(&mut result, &lowercase_title_case(text));
+                } else {
+                    push_escaped(&mut result, if *text == "->" { "→" } else { text });
+                }
+                // a `prefix:` keeps the next word first
+                first &= matches!(pieces.get(i + 1), Some(Piece::Punct(':')));
+            }
+            Piece::Punct(c) => push_escaped(&mut result, c.encode_utf8(&mut [0; 4])),
+            Piece::Space => result.push(' '),
+        }
+    }
+    // a trailing backslash would escape the `]` of the link
+    while ends_escaping(&result) {
+        result.pop();
+        result.truncate(result.trim_end().len());
+    }
+    result
+}
@@ -0,0 +1,269 @@
+use crate::config::Config;
+use crate::source::TokenInfo;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use time::macros::format_description;
+use time::{Duration, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+/// where we look for a token if the config doesn't say
+static TOKEN_FILE: &str = "cache/token";
+/// the service and user our token is stored under in the keyring
+static KEYRING: (&str, &str) = ("twirer", "github-token");
+
+/// Where we found the token
+#[derive(Debug)]
+pub enum Origin {
+    Env(&'static str),
+    Keyring,
+    File(PathBuf),
+    GhCli,
+    Prompt,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Env(var) => write!(f, "${var}"),
+            Origin::Keyring => f.write_str("the keyring"),
+            Origin::File(path) => write!(f, "{}", path.display()),
+            Origin::GhCli => f.write_str("`gh auth token`"),
+            Origin::Prompt => f.write_str("the prompt"),
+        }
+    }
+}
+
+/// the token from the environment, the keyring, the token file, the `gh`
+/// CLI or, failing all of those, a prompt
+pub fn token(config: &Config) -> Result<(String, Origin), Box<dyn Error>> {
+    for var in ["GH_TOKEN", "GITHUB_TOKEN"] {
+        if let Some(token) = std::env::var(var).ok().filter(|t| !t.trim().is_empty()) {
+            return Ok((token.trim().to_owned(), Origin::Env(var)));
+        }
+    }
+    if let Some(token) = from_keyring()? {
+        return Ok((token, Origin::Keyring));
+    }
+    let path = config
+        .token_file
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(TOKEN_FILE));
+    if let Some(token) = from_file(&path)? {
+        return Ok((token, Origin::File(path)));
+    }
+    if let Some(token) = from_gh() {
+        return Ok((token, Origin::GhCli));
+    }
+    Ok((prompt()?, Origin::Prompt))
+}
+
+fn keyring_entry() -> Result<keyring::Entry, Box<dyn Error>> {
+    let (service, user) = KEYRING;
+    keyring::Entry::new(service, user).map_err(|e| format!("the keyring: {e}").into())
+}
+
+/// the token stored with `twirer token store`, if there is a keyring we can use
+fn from_keyring() -> Result<Option<String>, Box<dyn Error>> {
+    match keyring_entry()?.get_password() {
+        Ok(token) if !token.trim().is_empty() => Ok(Some(token.trim().to_owned())),
+        Ok(_) => Err("the token in the keyring is empty, `twirer token store` it again".into()),
+        // no token stored, or no keyring on this machine
+        Err(
+            keyring::Error::NoEntry
+            | keyring::Error::NoStorageAccess(_)
+            | keyring::Error::PlatformFailure(_),
+        ) => Ok(None),
+        Err(e) => Err(format!("the keyring: {e}").into()),
+    }
+}
+
+/// ask for a token and put it in the keyring, where `token` looks first
+/// after the environment
+pub fn store() -> Result<(), Box<dyn Error>> {
+    let token = prompt()?;
+    keyring_entry()?
+        .set_password(&token)
+        .map_err(|e| format!("couldn't store the token in the keyring: {e}").into())
+}
+
+/// the token in `path`, refusing it if others may read the file
+fn from_file(path: &Path) -> Result<Option<String>, Box<dyn Error>> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Ok(None);
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path)?.permissions().mode();
+        if mode & 0o077 != 0 {
+            return Err(format!(
+                "{} is accessible by others (mode {:o}), `chmod 600` it",
+                path.display(),
+                mode & 0o777
+            )
+            .into());
+        }
+    }
+    let token = contents.trim();
+    if token.is_empty() {
+        return Err(format!("{} is empty", path.display()).into());
+    }
+    Ok(Some(token.to_owned()))
+}
+
+/// the token the `gh` CLI is logged in with, if it is installed and logged in
+fn from_gh() -> Option<String> {
+    let output = Command::new("gh")
+        .args(["auth", "token"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let token = String::from_utf8(output.stdout).ok()?;
+    (output.status.success() && !token.trim().is_empty()).then(|| token.trim().to_owned())
+}
+
+/// switch terminal echo on or off, ignoring failure (e.g. if stdin is a pipe)
+fn echo(on: bool) -> bool {
+    Command::new("stty")
+        .arg(if on { "echo" } else { "-echo" })
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// ask for the token without echoing it
+fn prompt() -> Result<String, Box<dyn Error>> {
+    let mut out = io::stderr();
+    out.write_all(b"token: ")?;
+    out.flush()?;
+    let hidden = echo(false);
+    let mut token = String::new();
+    let read = io::stdin().read_line(&mut token);
+    if hidden {
+        echo(true);
+        out.write_all(b"\n")?;
+    }
+    read?;
+    let token = token.trim();
+    if token.is_empty() {
+        return Err("no GitHub token given".into());
+    }
+    Ok(token.to_owned())
+}
+
+/// when a token expires, from GitHub's `2024-01-31 12:00:00 UTC` (or
+/// `+0100`) format
+fn expiry(expires: &str) -> Option<OffsetDateTime> {
+    let (date, time, zone) = match expires.split_whitespace().collect::<Vec<_>>()[..] {
+        [date, time, zone] => (date, time, zone),
+        _ => return None,
+    };
+    let moment = PrimitiveDateTime::parse(
+        &format!("{date} {time}"),
+        format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+    )
+    .ok()?;
+    let offset = match zone {
+        "UTC" => UtcOffset::UTC,
+        zone => UtcOffset::parse(
+            zone,
+            format_description!("[offset_hour sign:mandatory][offset_minute]"),
+        )
+        .ok()?,
+    };
+    Some(moment.assume_offset(offset))
+}
+
+/// things to fix about a token GitHub accepted
+pub fn problems(info: &TokenInfo, now: OffsetDateTime) -> Vec<String> {
+    let mut problems = Vec::new();
+    // we only read public PRs, so anything beyond reading is too much
+    let broad = info
+        .scopes
+        .iter()
+        .flatten()
+        .filter(|s| !s.starts_with("read:"))
+        .map(|s| format!("`{s}`"))
+        .collect::<Vec<_>>();
+    if !broad.is_empty() {
+        problems.push(format!(
+            "the token has the scopes {}, but reading public PRs needs none",
+            broad.join(", ")
+        ));
+    }
+    match info.expires.as_deref() {
+        None => problems.push("the token never expires".into()),
+        Some(expires) => match expiry(expires) {
+            None => problems.push(format!("can't make sense of the expiry `{expires}`")),
+            Some(at) if at <= now => problems.push(format!("the token expired at {expires}")),
+            Some(at) if at - now < Duration::WEEK => problems.push(format!(
+                "the token expires in {} hours, at {expires}",
+                (at - now).whole_hours()
+            )),
+            Some(_) => {}
+        },
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn info(scopes: Option<&[&str]>, expires: Option<&str>) -> TokenInfo {
+        TokenInfo {
+            login: "someone".into(),
+            scopes: scopes.map(|s| s.iter().map(|&s| s.to_owned()).collect()),
+            expires: expires.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn expiry() {
+        assert_eq!(
+            super::expiry("2026-10-31 12:00:00 UTC"),
+            Some(datetime!(2026-10-31 12:00 UTC))
+        );
+        assert_eq!(
+            super::expiry("2026-10-31 12:00:00 +0100"),
+            Some(datetime!(2026-10-31 11:00 UTC))
+        );
+        assert_eq!(super::expiry("2026-10-31T12:00:00Z"), None);
+        assert_eq!(super::expiry("2026-10-31 12:00:00 CET"), None);
+        assert_eq!(super::expiry("soon"), None);
+    }
+
+    #[test]
+    fn problems() {
+        let now = datetime!(2026-10-17 12:00 UTC);
+        let later = Some("2027-01-01 00:00:00 UTC");
+        assert!(super::problems(&info(None, later), now).is_empty());
+        assert!(super::problems(&info(Some(&["read:org"]), later), now).is_empty());
+        assert_eq!(
+            super::problems(&info(Some(&["repo", "read:org", "workflow"]), later), now),
+            ["the token has the scopes `repo`, `workflow`, but reading public PRs needs none"]
+        );
+        assert_eq!(
+            super::problems(&info(None, None), now),
+            ["the token never expires"]
+        );
+        assert_eq!(
+            super::problems(&info(None, Some("2026-10-17 12:00:00 UTC")), now),
+            ["the token expired at 2026-10-17 12:00:00 UTC"]
+        );
+        assert_eq!(
+            super::problems(&info(None, Some("2026-10-20 12:00:00 +0200")), now),
+            ["the token expires in 70 hours, at 2026-10-20 12:00:00 +0200"]
+        );
+        assert_eq!(
+            super::problems(&info(None, Some("next week")), now),
+            ["can't make sense of the expiry `next week`"]
+        );
+    }
+}
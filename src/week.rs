@@ -0,0 +1,368 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use time::format_description::well_known::Rfc3339;
+use time::{macros::format_description, Date, Duration, OffsetDateTime, Time, UtcOffset, Weekday};
+
+pub static WEEK_SPEC: &str = "cache/week_spec";
+/// the week of the last published issue
+pub static LAST_WEEK_SPEC: &str = "cache/last_week_spec";
+
+/// When one week ends and the next begins
+#[derive(Debug, Clone, Copy)]
+pub struct Cutoff {
+    pub time: Time,
+    pub offset: UtcOffset,
+}
+
+impl Cutoff {
+    /// from `HH:MM` and a UTC offset like `+02:00` or `UTC`; named zones
+    /// like `Europe/Berlin` aren't supported, the offset stays the same all
+    /// year
+    pub fn parse(time: &str, zone: &str) -> Result<Self, Box<dyn Error>> {
+        let time = Time::parse(time.trim(), format_description!("[hour]:[minute]"))
+            .map_err(|e| format!("`{time}` is not a `HH:MM` time: {e}"))?;
+        let offset = match zone.trim() {
+            "UTC" | "Z" => UtcOffset::UTC,
+            zone if zone.starts_with(|c: char| c.is_alphabetic()) => {
+                return Err(format!(
+                    "`{zone}` is a named time zone, only fixed UTC offsets like `+02:00` \
+                     are supported, so the cutoff doesn't follow daylight saving time"
+                )
+                .into())
+            }
+            zone => UtcOffset::parse(
+                zone,
+                format_description!("[offset_hour sign:mandatory]:[offset_minute]"),
+            )
+            .map_err(|e| format!("`{zone}` is not a `+HH:MM` UTC offset: {e}"))?,
+        };
+        Ok(Cutoff { time, offset })
+    }
+
+    /// the moment `date` begins for us
+    fn on(&self, date: Date) -> OffsetDateTime {
+        date.with_time(self.time).assume_offset(self.offset)
+    }
+}
+
+/// The merge times an issue covers, from (inclusive) until (exclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Week {
+    pub from: OffsetDateTime,
+    pub until: OffsetDateTime,
+}
+
+fn parse_date(date: &str) -> Result<Date, Box<dyn Error>> {
+    Date::parse(date.trim(), format_description!("[year]-[month]-[day]"))
+        .map_err(|e| format!("`{}` is not a `YYYY-MM-DD` date: {e}", date.trim()).into())
+}
+
+/// an ISO 8601 timestamp, or a date which starts at the cutoff
+fn parse_moment(moment: &str, cutoff: &Cutoff) -> Result<OffsetDateTime, Box<dyn Error>> {
+    let moment = moment.trim();
+    if moment.contains('T') {
+        OffsetDateTime::parse(moment, &Rfc3339)
+            .map_err(|e| format!("`{moment}` is not an ISO 8601 timestamp: {e}").into())
+    } else {
+        Ok(cutoff.on(parse_date(moment)?))
+    }
+}
+
+fn format_moment(moment: OffsetDateTime) -> String {
+    moment
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| moment.to_string())
+}
+
+pub fn parse_weekday(day: &str) -> Result<Weekday, Box<dyn Error>> {
+    Ok(match &day.to_lowercase()[..] {
+        "monday" => Weekday::Monday,
+        "tuesday" => Weekday::Tuesday,
+        "wednesday" => Weekday::Wednesday,
+        "thursday" => Weekday::Thursday,
+        "friday" => Weekday::Friday,
+        "saturday" => Weekday::Saturday,
+        "sunday" => Weekday::Sunday,
+        _ => return Err(format!("`{day}` is not a weekday").into()),
+    })
+}
+
+impl Week {
+    /// parse `from..until`, where dates (as in older week specs) start at
+    /// the cutoff
+    pub fn parse(spec: &str, cutoff: &Cutoff) -> Result<Self, Box<dyn Error>> {
+        let (from, until) = spec.trim().split_once("..").ok_or_else(|| {
+            format!(
+                "malformed week spec `{}`, expected `from..until`",
+                spec.trim()
+            )
+        })?;
+        let week = Week {
+            from: parse_moment(from, cutoff)?,
+            until: parse_moment(until, cutoff)?,
+        };
+        if week.until <= week.from {
+            return Err(format!("the week spec `{week}` ends before it starts").into());
+        }
+        Ok(week)
+    }
+
+    /// the `weeks` weeks after this one
+    pub fn next(&self, weeks: u8) -> Self {
+        Week {
+            from: self.until,
+            until: self.until + Duration::WEEK * weeks,
+        }
+    }
+
+    /// the week before this one
+    pub fn prev(&self) -> Self {
+        Week {
+            from: self.from - Duration::WEEK,
+            until: self.from,
+        }
+    }
+
+    /// the `weeks` weeks of an issue published on `date`, which end at the
+    /// cutoff on the last `ends_on` before it
+    pub fn of_issue(date: Date, ends_on: Weekday, weeks: u8, cutoff: &Cutoff) -> Self {
+        let mut until = date - Duration::DAY;
+        while until.weekday() != ends_on {
+            until -= Duration::DAY;
+        }
+        let until = cutoff.on(until);
+        Week {
+            from: until - Duration::WEEK * weeks,
+            until,
+        }
+    }
+
+    /// the issue date from the `Date: YYYY-MM-DD` line of a draft
+    pub fn issue_date(draft: &str) -> Result<Date, Box<dyn Error>> {
+        let date = draft
+            .lines()
+            .find_map(|l| l.strip_prefix("Date: "))
+            .ok_or("`Date:` not found in draft")?;
+        parse_date(date)
+    }
+
+    /// the range for a `merged:` search; GitHub includes both ends, so we
+    /// stop a second short of `until`
+    pub fn query(&self) -> String {
+        let last = self.until - Duration::SECOND;
+        format!("{}..{}", format_moment(self.from), format_moment(last))
+    }
+
+    /// split into days, a day into hours, for searches with too many results
+    pub fn split(&self) -> Option<Vec<Week>> {
+        let span = self.until - self.from;
+        let step = if span > Duration::DAY {
+            Duration::DAY
+        } else if span > Duration::HOUR {
+            Duration::HOUR
+        } else {
+            return None;
+        };
+        let mut parts = Vec::new();
+        let mut from = self.from;
+        while from < self.until {
+            let until = (from + step).min(self.until);
+            parts.push(Week { from, until });
+            from = until;
+        }
+        Some(parts)
+    }
+
+    /// things that are likely wrong with this week, given the last one
+    pub fn problems(&self, last: Option<&Week>) -> Vec<String> {
+        let mut problems = Vec::new();
+        let span = self.until - self.from;
+        if span != Duration::WEEK && span != Duration::WEEK * 2 {
+            problems.push(format!(
+                "`{self}` spans {:.1} days, not one or two weeks",
+                span.as_seconds_f64() / 86400.0
+            ));
+        }
+        if let Some(last) = last {
+            if self.from < last.until {
+                problems.push(format!("`{self}` overlaps the last issue's `{last}`"));
+            } else if self.from > last.until {
+                problems.push(format!(
+                    "`{self}` leaves a gap after the last issue's `{last}`"
+                ));
+            }
+        }
+        problems
+    }
+}
+
+impl fmt::Display for Week {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}..{}",
+            format_moment(self.from),
+            format_moment(self.until)
+        )
+    }
+}
+
+pub fn read(cutoff: &Cutoff) -> Result<Week, Box<dyn Error>> {
+    let spec = fs::read_to_string(WEEK_SPEC).map_err(|e| format!("{WEEK_SPEC}: {e}"))?;
+    Week::parse(&spec, cutoff).map_err(|e| format!("{WEEK_SPEC}: {e}").into())
+}
+
+/// the week of the last issue, if we know it
+pub fn read_last(cutoff: &Cutoff) -> Option<Week> {
+    Week::parse(&fs::read_to_string(LAST_WEEK_SPEC).ok()?, cutoff).ok()
+}
+
+pub fn write(week: &Week) -> Result<(), Box<dyn Error>> {
+    let _ = fs::create_dir_all("cache"); // ignore possible errors
+    fs::write(WEEK_SPEC, week.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime};
+
+    fn utc() -> Cutoff {
+        Cutoff::parse("00:00", "UTC").unwrap()
+    }
+
+    #[test]
+    fn cutoff() {
+        let cutoff = Cutoff::parse("18:30", "+02:00").unwrap();
+        assert_eq!(
+            cutoff.on(date!(2026 - 10 - 13)),
+            datetime!(2026-10-13 18:30 +2)
+        );
+        assert!(Cutoff::parse("6pm", "UTC").is_err());
+        for zone in ["CEST", "Europe/Berlin"] {
+            let err = Cutoff::parse("18:00", zone).unwrap_err().to_string();
+            assert!(err.contains("is a named time zone"), "{err}");
+        }
+        assert!(Cutoff::parse("18:00", "+2").is_err());
+    }
+
+    #[test]
+    fn parse() {
+        let week = Week {
+            from: datetime!(2026-10-13 00:00 UTC),
+            until: datetime!(2026-10-20 00:00 UTC),
+        };
+        assert_eq!(
+            Week::parse("2026-10-13..2026-10-20\n", &utc()).unwrap(),
+            week
+        );
+        let spec = "2026-10-13T00:00:00Z..2026-10-20T00:00:00Z";
+        assert_eq!(Week::parse(spec, &utc()).unwrap(), week);
+        assert_eq!(week.to_string(), spec);
+        // dates start at the cutoff
+        let cutoff = Cutoff::parse("12:00", "-05:00").unwrap();
+        assert_eq!(
+            Week::parse("2026-10-13..2026-10-20T12:00:00-05:00", &cutoff)
+                .unwrap()
+                .from,
+            datetime!(2026-10-13 17:00 UTC)
+        );
+        let error = |spec| Week::parse(spec, &utc()).unwrap_err().to_string();
+        assert_eq!(
+            error("2026-10-13"),
+            "malformed week spec `2026-10-13`, expected `from..until`"
+        );
+        assert!(
+            error("2026-10-13..2026-10-32").starts_with("`2026-10-32` is not a `YYYY-MM-DD` date")
+        );
+        assert!(error("2026-10-13T00:00..2026-10-20").contains("is not an ISO 8601 timestamp"));
+        assert_eq!(
+            error("2026-10-20..2026-10-13"),
+            "the week spec `2026-10-20T00:00:00Z..2026-10-13T00:00:00Z` ends before it starts"
+        );
+    }
+
+    #[test]
+    fn next_and_previous() {
+        let week = Week::parse("2026-10-13..2026-10-20", &utc()).unwrap();
+        assert_eq!(
+            week.next(1).to_string(),
+            "2026-10-20T00:00:00Z..2026-10-27T00:00:00Z"
+        );
+        assert_eq!(
+            week.next(2).to_string(),
+            "2026-10-20T00:00:00Z..2026-11-03T00:00:00Z"
+        );
+        assert_eq!(
+            week.prev().to_string(),
+            "2026-10-06T00:00:00Z..2026-10-13T00:00:00Z"
+        );
+        assert_eq!(week.query(), "2026-10-13T00:00:00Z..2026-10-19T23:59:59Z");
+        // the issue of Wednesday the 21st covers the week until Tuesday
+        assert_eq!(
+            Week::of_issue(date!(2026 - 10 - 21), Weekday::Tuesday, 1, &utc()),
+            week
+        );
+        // ...but not the Tuesday of the issue itself
+        assert_eq!(
+            Week::of_issue(date!(2026 - 10 - 20), Weekday::Tuesday, 2, &utc()).to_string(),
+            "2026-09-29T00:00:00Z..2026-10-13T00:00:00Z"
+        );
+        assert_eq!(
+            Week::issue_date("Title: This Week in Rust 600\nDate: 2026-10-21\n").unwrap(),
+            date!(2026 - 10 - 21)
+        );
+        assert!(Week::issue_date("Title: This Week in Rust 600\n").is_err());
+    }
+
+    #[test]
+    fn split() {
+        let week = Week::parse("2026-10-13T00:00:00Z..2026-10-15T12:00:00Z", &utc()).unwrap();
+        let days = week.split().unwrap();
+        assert_eq!(
+            days.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "2026-10-13T00:00:00Z..2026-10-14T00:00:00Z",
+                "2026-10-14T00:00:00Z..2026-10-15T00:00:00Z",
+                "2026-10-15T00:00:00Z..2026-10-15T12:00:00Z",
+            ]
+        );
+        let hours = days[2].split().unwrap();
+        assert_eq!(hours.len(), 12);
+        assert_eq!(
+            hours[11].to_string(),
+            "2026-10-15T11:00:00Z..2026-10-15T12:00:00Z"
+        );
+        assert_eq!(hours[0].split(), None);
+    }
+
+    #[test]
+    fn problems() {
+        let last = Week::parse("2026-10-06..2026-10-13", &utc()).unwrap();
+        let week = last.next(1);
+        assert!(week.problems(Some(&last)).is_empty());
+        assert!(last.next(2).problems(Some(&last)).is_empty());
+        assert!(week.problems(None).is_empty());
+        let parse = |spec| Week::parse(spec, &utc()).unwrap();
+        assert_eq!(
+            parse("2026-10-13..2026-10-23").problems(Some(&last)),
+            ["`2026-10-13T00:00:00Z..2026-10-23T00:00:00Z` spans 10.0 days, not one or two weeks"]
+        );
+        assert_eq!(
+            parse("2026-10-12..2026-10-19").problems(Some(&last)),
+            [
+                "`2026-10-12T00:00:00Z..2026-10-19T00:00:00Z` overlaps the last issue's \
+              `2026-10-06T00:00:00Z..2026-10-13T00:00:00Z`"
+            ]
+        );
+        assert_eq!(
+            parse("2026-10-14..2026-10-21").problems(Some(&last)),
+            [
+                "`2026-10-14T00:00:00Z..2026-10-21T00:00:00Z` leaves a gap after the last issue's \
+              `2026-10-06T00:00:00Z..2026-10-13T00:00:00Z`"
+            ]
+        );
+    }
+}
@@ -0,0 +1,520 @@
+//! Golden tests for the title formatting, with titles from past issues and
+//! made up ones for the edge cases
+
+use std::collections::HashSet;
+use twirer::title::{
+    code_rule, escape_markdown, explain_title, format_title, CodeRule, TitleOptions,
+};
+
+/// (title as merged, title as it should appear in TWiR), real titles only
+static TITLES: &[(&str, &str)] = &[
+    ("Stabilize `const_option`", "stabilize `const_option`"),
+    ("Add `Option::take_if`", "add `Option::take_if`"),
+    (
+        "Fix ICE when using `impl Trait` in associated type",
+        "fix ICE when using `impl Trait` in associated type",
+    ),
+    (
+        "Implement `From<[T; N]>` for `Vec<T>`",
+        "implement `From<[T; N]>` for `Vec<T>`",
+    ),
+    (
+        "Make `std::io::Error` smaller",
+        "make `std::io::Error` smaller",
+    ),
+    (
+        "rustdoc: Show `#[doc(hidden)]` items with `--document-hidden-items`",
+        "rustdoc: show `#[doc(hidden)]` items with `--document-hidden-items`",
+    ),
+    (
+        "Remove unused `rustc_span` dependency",
+        "remove unused `rustc_span` dependency",
+    ),
+    (
+        "Don't lint `needless_return` in async blocks",
+        "don't lint `needless_return` in async blocks",
+    ),
+    (
+        "Optimize `Vec::extend_from_slice` for `Copy` types",
+        "optimize `Vec::extend_from_slice` for `Copy` types",
+    ),
+    (
+        "Implement RFC 3137: `let else`",
+        "implement RFC 3137: `let else`",
+    ),
+    (
+        "Speed up rustc_metadata decoding",
+        "speed up `rustc_metadata` decoding",
+    ),
+    (
+        "Use `is_some_and` instead of `map_or(false, ..)`",
+        "use `is_some_and` instead of `map_or(false, ..)`",
+    ),
+    (
+        "Add a new lint: manual_is_ascii_check",
+        "add a new lint: `manual_is_ascii_check`",
+    ),
+    ("Update cargo", "update cargo"),
+    ("Fix typo in docs", "fix typo in docs"),
+    (
+        "Allow #[repr(align(N))] on enums",
+        "allow `#[repr(align(N))]` on enums",
+    ),
+    (
+        "Stabilize async fn in traits",
+        "stabilize async fn in traits",
+    ),
+    ("Make Vec::new const", "make `Vec::new` const"),
+    ("Add Iterator::map_windows", "add `Iterator::map_windows`"),
+    (
+        "new lint: needless_pass_by_ref_mut",
+        "new lint: `needless_pass_by_ref_mut`",
+    ),
+    ("Account for -> in closures", "account for → in closures"),
+    ("Rename `Gen` to `Coroutine`", "rename `Gen` to `Coroutine`"),
+    ("Bump LLVM to 17", "bump LLVM to 17"),
+    (
+        "Implement Read for VecDeque<u8>",
+        "implement Read for `VecDeque<u8>`",
+    ),
+    (
+        "Deny unsafe_op_in_unsafe_fn in std",
+        "deny `unsafe_op_in_unsafe_fn` in std",
+    ),
+    (
+        "Fix (some) unsoundness in Rc::from_raw",
+        "fix (some) unsoundness in `Rc::from_raw`",
+    ),
+    (
+        "Coverage: Treat #[coverage(off)] as inherited",
+        "coverage: treat `#[coverage(off)]` as inherited",
+    ),
+    ("Add `[T]::as_simd`", "add `[T]::as_simd`"),
+    (
+        "Suggest `Box::pin` when a future isn't pinned",
+        "suggest `Box::pin` when a future isn't pinned",
+    ),
+    (
+        "Stop using `Vec` in hot paths",
+        "stop using `Vec` in hot paths",
+    ),
+    (
+        "Add support for the `x86_64h-apple-darwin` target",
+        "add support for the `x86_64h-apple-darwin` target",
+    ),
+    (
+        "Don't emit `unused_qualifications` for `use` items",
+        "don't emit `unused_qualifications` for `use` items",
+    ),
+    ("Make `BTreeMap::new` const", "make `BTreeMap::new` const"),
+    (
+        "Fix incorrect suggestion for `impl Trait` in argument position",
+        "fix incorrect suggestion for `impl Trait` in argument position",
+    ),
+    ("Update to LLVM 18.1", "update to LLVM 18.1"),
+    (
+        "Migrate `rustc_hir_analysis` to translatable diagnostics",
+        "migrate `rustc_hir_analysis` to translatable diagnostics",
+    ),
+    (
+        "Improve `Debug` impl for `Duration`",
+        "improve `Debug` impl for `Duration`",
+    ),
+    (
+        "Add missing `Send` bound on `JoinHandle`",
+        "add missing `Send` bound on `JoinHandle`",
+    ),
+    (
+        "Document panic in `Vec::swap_remove`",
+        "document panic in `Vec::swap_remove`",
+    ),
+    (
+        "Rustdoc-Json: Add `is_stripped` field",
+        "rustdoc-json: add `is_stripped` field",
+    ),
+    (
+        "Implement `IntoIterator` for `Box<[T]>`",
+        "implement `IntoIterator` for `Box<[T]>`",
+    ),
+    (
+        "Use `core::ptr::copy_nonoverlapping` in `slice::copy_from_slice`",
+        "use `core::ptr::copy_nonoverlapping` in `slice::copy_from_slice`",
+    ),
+    (
+        "Fix std::fs::canonicalize on Windows",
+        "fix `std::fs::canonicalize` on Windows",
+    ),
+    (
+        "Make `#[track_caller]` work on closures",
+        "make `#[track_caller]` work on closures",
+    ),
+    (
+        "Lower `async fn` return types eagerly",
+        "lower `async fn` return types eagerly",
+    ),
+];
+
+/// code the title leaves bare
+static CODE_IN_TEXT: &[(&str, &str)] = &[
+    (
+        "Don't ICE on Foo::bar() with incorrect generics",
+        "don't ICE on `Foo::bar()` with incorrect generics",
+    ),
+    (
+        "Use len() instead of iterating",
+        "use `len()` instead of iterating",
+    ),
+    (
+        "Improve diagnostics for HashMap<K, V> misuse",
+        "improve diagnostics for `HashMap<K, V>` misuse",
+    ),
+    (
+        "Lint: warn on unused_must_use for Box<dyn Future>",
+        "lint: warn on `unused_must_use` for `Box<dyn Future>`",
+    ),
+    (
+        "Use is_some_and instead of map_or(false, ..) in std",
+        "use `is_some_and` instead of `map_or(false, ..)` in std",
+    ),
+    (
+        "Add --document-hidden-items to rustdoc",
+        "add `--document-hidden-items` to rustdoc",
+    ),
+    (
+        "Support -Zshare-generics for cfg(unix) in library/core/src",
+        "support `-Zshare-generics` for `cfg(unix)` in `library/core/src`",
+    ),
+    (
+        "Use .clone() in println! for HashMap",
+        "use `.clone()` in `println!` for `HashMap`",
+    ),
+    (
+        "Don't lint (foo_bar) in macros",
+        "don't lint (`foo_bar`) in macros",
+    ),
+    (
+        "Rename \"foo_bar\" to \"baz\"",
+        "rename \"`foo_bar`\" to \"baz\"",
+    ),
+];
+
+/// words next to code that stay text
+static NOT_CODE_TITLES: &[(&str, &str)] = &[
+    ("Fix ICE (#12345)", "fix ICE (#12345)"),
+    ("Explain 'static lifetimes", "explain 'static lifetimes"),
+    (
+        "Support `async` closures (part 2)",
+        "support `async` closures (part 2)",
+    ),
+    (
+        "docs.rs: Fix build of crates",
+        "docs.rs: fix build of crates",
+    ),
+    (
+        "Support FreeBSD and WebAssembly on GitHub CI",
+        "support FreeBSD and WebAssembly on GitHub CI",
+    ),
+];
+
+/// code spans the title already has, broken ones included
+static EXISTING_CODE: &[(&str, &str)] = &[
+    ("Fix `Vec<T>`'s docs", "fix `Vec<T>`'s docs"),
+    (
+        "Lint `impl Trait for Foo` blocks",
+        "lint `impl Trait for Foo` blocks",
+    ),
+    ("Handle [`Option::map`] links", "handle `Option::map` links"),
+    (
+        "Add `Vec::new`, `Vec::with_capacity`: make them const",
+        "add `Vec::new`, `Vec::with_capacity`: make them const",
+    ),
+    ("Fix `missing backtick", "fix missing backtick"),
+    ("Add `a` ` b", "add `a` b"),
+    ("Use ``a`b`` in docs", "use ``a`b`` in docs"),
+];
+
+/// markdown in the text, escaped or not
+static MARKDOWN: &[(&str, &str)] = &[
+    (
+        "Don't emit * or | in [docs] tables",
+        "don't emit \\* or \\| in \\[docs\\] tables",
+    ),
+    ("# of items is wrong", "\\# of items is wrong"),
+    (
+        "Keep escaped \\_ and \\[brackets\\] as they are",
+        "keep escaped \\_ and \\[brackets\\] as they are",
+    ),
+    ("Fix path ending in \\", "fix path ending in"),
+];
+
+/// what surrounds the title itself
+static SURROUNDINGS: &[(&str, &str)] = &[
+    (
+        "  Trim surrounding whitespace  ",
+        "trim surrounding whitespace",
+    ),
+    (
+        "Mark `size_of` as `#[rustc_const_stable]`.",
+        "mark `size_of` as `#[rustc_const_stable]`.",
+    ),
+];
+
+/// made up titles for the edge cases, by what they exercise
+static EDGE_CASES: &[&[(&str, &str)]] = &[
+    CODE_IN_TEXT,
+    NOT_CODE_TITLES,
+    EXISTING_CODE,
+    MARKDOWN,
+    SURROUNDINGS,
+];
+
+/// like `TITLES`, but with `KEYWORDS` as `code_keywords`
+static KEYWORD_TITLES: &[(&str, &str)] = &[
+    ("Bump LLVM to 17", "bump `LLVM` to 17"),
+    (
+        "Implement Read for VecDeque<u8>",
+        "implement `Read` for `VecDeque<u8>`",
+    ),
+    ("Update to LLVM 18.1", "update to `LLVM` 18.1"),
+    ("Make Vec::new const", "make `Vec::new` const"),
+    (
+        "Stop using `Vec` in hot paths",
+        "stop using `Vec` in hot paths",
+    ),
+    (
+        "Fix (some) unsoundness in Rc::from_raw",
+        "fix (some) unsoundness in `Rc::from_raw`",
+    ),
+];
+
+/// (word, the rule that makes it code), one or more per rule
+static RULES: &[(&str, CodeRule)] = &[
+    ("LLVM", CodeRule::Keyword),
+    ("#[must_use]", CodeRule::Attribute),
+    ("cfg(unix)", CodeRule::Cfg),
+    ("cfg!(windows)", CodeRule::Cfg),
+    ("clippy::needless_return", CodeRule::Lint),
+    ("rustdoc::broken_intra_doc_links", CodeRule::Lint),
+    ("std::mem", CodeRule::Path),
+    (".clone()", CodeRule::Method),
+    (".map_or(false, ..)", CodeRule::Method),
+    ("println!", CodeRule::Macro),
+    ("vec![]", CodeRule::Macro),
+    ("size_of(T)", CodeRule::Call),
+    ("x86_64-unknown-linux-gnu", CodeRule::TargetTriple),
+    ("aarch64-apple-darwin", CodeRule::TargetTriple),
+    ("wasm32-wasip1", CodeRule::TargetTriple),
+    ("library/core/src", CodeRule::FilePath),
+    ("Cargo.toml", CodeRule::FilePath),
+    ("--document-hidden-items", CodeRule::LongFlag),
+    ("-Zunstable-options", CodeRule::CompilerFlag),
+    ("-Copt-level=3", CodeRule::CompilerFlag),
+    ("Box<dyn Error>", CodeRule::Generics),
+    ("is_some_and", CodeRule::SnakeCase),
+    ("HashMap", CodeRule::CamelCase),
+    ("JoinHandle", CodeRule::CamelCase),
+];
+
+/// words that look a bit like code, but aren't
+static NOT_CODE: &[&str] = &[
+    "ICE",
+    "Windows",
+    "(part",
+    "and/or",
+    "read/write/execute",
+    "x86",
+    "-",
+    "--",
+    "1.0",
+    "e.g.",
+    "LLVM's",
+    "macOS",
+    "FreeBSD",
+    "GitHub",
+    "WebAssembly",
+    "PowerPC",
+    "arm-specific",
+    "riscv-only",
+    "it!",
+    "docs.rs",
+    "blog.rust-lang.org",
+];
+
+static KEYWORDS: &[&str] = &["Vec", "Rc", "Read", "LLVM"];
+
+fn check(titles: &[(&str, &str)], options: &TitleOptions) {
+    let mut failures = Vec::new();
+    for &(title, expected) in titles {
+        let formatted = format_title(title, options);
+        if formatted != expected {
+            failures.push(format!(
+                "{title:?}\n  expected {expected:?}\n       got {formatted:?}"
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn titles() {
+    check(TITLES, &TitleOptions::default());
+}
+
+#[test]
+fn code_in_text() {
+    check(CODE_IN_TEXT, &TitleOptions::default());
+}
+
+#[test]
+fn not_code_titles() {
+    check(NOT_CODE_TITLES, &TitleOptions::default());
+}
+
+#[test]
+fn existing_code() {
+    check(EXISTING_CODE, &TitleOptions::default());
+}
+
+#[test]
+fn markdown() {
+    check(MARKDOWN, &TitleOptions::default());
+}
+
+#[test]
+fn surroundings() {
+    check(SURROUNDINGS, &TitleOptions::default());
+}
+
+#[test]
+fn title_case() {
+    check(
+        &[
+            ("Cross-Crate Inlining", "cross-crate Inlining"),
+            ("LLVM-Based codegen", "LLVM-based codegen"),
+            ("I think it works", "I think it works"),
+        ],
+        &TitleOptions::default(),
+    );
+}
+
+#[test]
+fn keyword_titles() {
+    let options = TitleOptions {
+        code_keywords: KEYWORDS
+            .iter()
+            .map(|&k| k.to_owned())
+            .collect::<HashSet<_>>(),
+        ..TitleOptions::default()
+    };
+    check(KEYWORD_TITLES, &options);
+}
+
+fn keyword_options() -> TitleOptions {
+    TitleOptions {
+        code_keywords: ["LLVM".to_owned()].into_iter().collect(),
+        ..TitleOptions::default()
+    }
+}
+
+#[test]
+fn rules() {
+    let options = keyword_options();
+    for &(word, rule) in RULES {
+        assert_eq!(code_rule(word, &options), Some(rule), "{word:?}");
+    }
+    for &word in NOT_CODE {
+        assert_eq!(code_rule(word, &options), None, "{word:?}");
+    }
+}
+
+#[test]
+fn disabled_rules() {
+    for &(word, rule) in RULES {
+        let mut options = keyword_options();
+        options.disabled_rules.insert(rule);
+        assert_ne!(code_rule(word, &options), Some(rule), "{word:?}");
+    }
+    let mut options = TitleOptions::default();
+    options.disabled_rules.insert(CodeRule::CamelCase);
+    assert_eq!(
+        format_title("Speed up HashMap", &options),
+        "speed up HashMap"
+    );
+}
+
+#[test]
+fn prefixes() {
+    let options = TitleOptions {
+        prefixes: ["codegen_gcc".to_owned()].into_iter().collect(),
+        ..TitleOptions::default()
+    };
+    assert_eq!(
+        format_title("codegen_gcc: Fix ICE on unsized locals", &options),
+        "codegen\\_gcc: fix ICE on unsized locals"
+    );
+    // escaped as `display_title` puts it in front
+    let title = format!("{}: Fix ICE", escape_markdown("codegen_gcc"));
+    assert_eq!(title, "codegen\\_gcc: Fix ICE");
+    assert_eq!(format_title(&title, &options), "codegen\\_gcc: fix ICE");
+    // what is already escaped stays as it is
+    assert_eq!(escape_markdown("a\\_b [c]"), "a\\_b \\[c\\]");
+}
+
+#[test]
+fn rule_names() {
+    for &rule in CodeRule::ALL {
+        assert_eq!(rule.to_string().parse::<CodeRule>(), Ok(rule));
+    }
+    assert!("camelcase".parse::<CodeRule>().is_err());
+}
+
+#[test]
+fn explain() {
+    assert_eq!(
+        explain_title(
+            "Fix clippy::needless_return on aarch64-apple-darwin",
+            &keyword_options()
+        ),
+        [
+            ("Fix", None),
+            ("clippy::needless_return", Some(CodeRule::Lint)),
+            ("on", None),
+            ("aarch64-apple-darwin", Some(CodeRule::TargetTriple)),
+        ]
+    );
+}
+
+#[test]
+fn escaped_text() {
+    for &(title, _) in TITLES.iter().chain(EDGE_CASES.iter().copied().flatten()) {
+        let formatted = format_title(title, &TitleOptions::default());
+        // the text between the code spans
+        for text in formatted.split('`').step_by(2) {
+            let mut escaped = false;
+            for c in text.chars() {
+                assert!(
+                    escaped || !"_*[]<>|".contains(c),
+                    "unescaped `{c}` in {formatted:?}"
+                );
+                escaped = !escaped && c == '\\';
+            }
+        }
+    }
+}
+
+#[test]
+fn balanced_backticks() {
+    for &(title, _) in TITLES.iter().chain(EDGE_CASES.iter().copied().flatten()) {
+        let formatted = format_title(title, &TitleOptions::default());
+        let mut fences = formatted
+            .split(|c| c != '`')
+            .filter(|f| !f.is_empty())
+            .collect::<Vec<_>>();
+        // every fence must be closed by one of the same length
+        while let Some(fence) = fences.first() {
+            let close = fences[1..].iter().position(|f| f == fence);
+            assert!(close.is_some(), "unbalanced backticks in {formatted:?}");
+            fences.drain(..=close.unwrap() + 1);
+        }
+    }
+}
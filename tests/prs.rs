@@ -0,0 +1,275 @@
+//! End-to-end tests of the commands, with `prs` and `filter` running against
+//! the recorded search responses in `tests/fixtures/prs`
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// a fresh directory for the cache of one test
+fn workspace(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("twirer-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("cache")).unwrap();
+    dir
+}
+
+fn twirer(workspace: &Path, args: &[&str]) -> String {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/prs");
+    let output = Command::new(env!("CARGO_BIN_EXE_twirer"))
+        .arg("--fixtures")
+        .arg(fixtures)
+        .args(args)
+        .env("TWIRER_WORKSPACE", workspace)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        output.status.success(),
+        "twirer {args:?} failed:\n{stdout}{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    stdout
+}
+
+#[test]
+fn prs_and_filter() {
+    let dir = workspace("prs");
+    let cache = dir.join("cache");
+    fs::write(cache.join("week_spec"), "2026-10-13..2026-10-20").unwrap();
+    fs::write(cache.join("config.toml"), "ignore = [\"needless\"]\n").unwrap();
+
+    let out = twirer(&dir, &["prs"]);
+    assert!(out.contains("added https://github.com/rust-lang/rust/pull/140003 from rollup"));
+    assert!(out.contains(
+        "warning: https://github.com/rust-lang/rust/pull/140004 from rollup isn't merged"
+    ));
+    let num_prs = fs::read_to_string(cache.join("num_prs")).unwrap();
+    assert!(num_prs.starts_with("5 pull requests"), "{num_prs}");
+
+    twirer(&dir, &["filter"]);
+    assert_eq!(
+        fs::read_to_string(cache.join("filteredprs")).unwrap(),
+        "#### Compiler\n\n\
+         * [stabilize `let_chains` in the 2024 edition](https://github.com/rust-lang/rust/pull/140001)\n\n\
+         #### Library\n\n\
+         * [add `Vec::pop_if`](https://github.com/rust-lang/rust/pull/140003)\n\n\
+         #### Cargo\n\n\
+         * [cargo: add `--message-format` to cargo fix](https://github.com/rust-lang/cargo/pull/15001)\n"
+    );
+    let dropped = fs::read_to_string(cache.join("dropped.json")).unwrap();
+    assert!(dropped.contains("rust-lang/rust/pull/140002"), "{dropped}");
+    assert!(
+        dropped.contains("rust-lang/rust-clippy/pull/14001"),
+        "{dropped}"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unrecorded_query() {
+    let dir = workspace("unrecorded");
+    fs::write(dir.join("cache/week_spec"), "2026-10-20..2026-10-27").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_twirer"))
+        .args(["--fixtures", "tests/fixtures/prs", "prs"])
+        .env("TWIRER_WORKSPACE", &dir)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no recorded response"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn broken_config() {
+    let dir = workspace("config");
+    fs::write(dir.join("cache/week_spec"), "2026-10-13..2026-10-20").unwrap();
+    fs::write(dir.join("cache/config.toml"), "ignroe = [\"needless\"]\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_twirer"))
+        .args(["--fixtures", "tests/fixtures/prs", "prs"])
+        .env("TWIRER_WORKSPACE", &dir)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown field `ignroe`"));
+    assert!(!dir.join("cache/prs.json").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn stale_progress() {
+    let dir = workspace("progress");
+    fs::write(dir.join("cache/week_spec"), "2026-10-13..2026-10-20").unwrap();
+    // a finished search of the same query, but left over from another run
+    let query = "is:pr org:rust-lang is:merged merged:2026-10-13T00:00:00Z..2026-10-19T23:59:59Z";
+    let progress = format!(
+        r#"{{"week": "2026-10-06T00:00:00Z..2026-10-13T00:00:00Z", "queries": [],
+            "searches": {{"{query}": {{"total": 0, "pages": 1, "complete": true, "prs": []}}}}}}"#
+    );
+    fs::write(dir.join("cache/progress.json"), progress).unwrap();
+    let out = twirer(&dir, &["prs"]);
+    assert!(out.contains("discarding the progress"), "{out}");
+    let num_prs = fs::read_to_string(dir.join("cache/num_prs")).unwrap();
+    assert!(num_prs.starts_with("5 pull requests"), "{num_prs}");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn zero_weeks() {
+    let dir = workspace("weeks");
+    let spec = "2026-10-13T00:00:00Z..2026-10-20T00:00:00Z";
+    fs::write(dir.join("cache/week_spec"), spec).unwrap();
+    for args in [["week", "next", "0"], ["week", "draft", "0"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_twirer"))
+            .args(args)
+            .env("TWIRER_WORKSPACE", &dir)
+            .output()
+            .unwrap();
+        assert!(!output.status.success(), "{args:?}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("usage: twirer week"));
+    }
+    assert_eq!(
+        fs::read_to_string(dir.join("cache/week_spec")).unwrap(),
+        spec
+    );
+    twirer(&dir, &["week", "next", "2"]);
+    assert_eq!(
+        fs::read_to_string(dir.join("cache/week_spec")).unwrap(),
+        "2026-10-20T00:00:00Z..2026-11-03T00:00:00Z"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn token_check() {
+    let dir = workspace("token");
+    let out = twirer(&dir, &["token", "check"]);
+    assert_eq!(
+        out,
+        "the token belongs to twir-bot\n\
+         it has the scopes repo, read:org\n\
+         it expires at 2099-01-01 00:00:00 UTC\n\
+         warning: the token has the scopes `repo`, but reading public PRs needs none\n"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// a workspace with a config and a checkout with the new draft, for
+/// `start` and `push`
+fn with_draft(name: &str, number: &str) -> (PathBuf, PathBuf) {
+    let dir = workspace(name);
+    let cache = dir.join("cache");
+    fs::write(cache.join("week_spec"), "2026-10-13..2026-10-20").unwrap();
+    fs::write(
+        cache.join("config.toml"),
+        format!(
+            "ignore = [\"needless\"]\neditor = \"vim\"\nfirefox = \"firefox\"\ntwir = {:?}\n",
+            dir.join("twir")
+        ),
+    )
+    .unwrap();
+    let draft = dir.join("twir/draft/2026-10-21-this-week-in-rust.md");
+    fs::create_dir_all(draft.parent().unwrap()).unwrap();
+    fs::write(
+        &draft,
+        format!(
+            "Title: This Week in Rust {number}\nNumber: {number}\nDate: 2026-10-21\n\n\
+             ## Crate of the Week\n\n<!-- COTW goes here -->\n\n\
+             ## Updates from the Rust Project\n\n<!-- Rust updates go here -->\n\n\
+             ## Quote of the Week\n\n<!-- QOTW goes here -->\n"
+        ),
+    )
+    .unwrap();
+    (dir, draft)
+}
+
+#[test]
+fn start() {
+    let (dir, draft) = with_draft("start", "600");
+    let out = twirer(&dir, &["start"]);
+    // with fixtures, we don't touch the checkout or start anything
+    for command in [
+        "git checkout master",
+        "git pull",
+        "firefox --new-tab",
+        "vim ",
+    ] {
+        assert!(out.contains(&format!("Not running {command}")), "{out}");
+    }
+    assert!(!out.contains("Running "), "{out}");
+    let draft = fs::read_to_string(draft).unwrap();
+    assert!(!draft.contains("goes here"), "{draft}");
+    assert!(draft.contains(
+        "5 pull requests were [merged in the last week][merged]\n\n\
+         [merged]: https://github.com/search?q=is%3Apr+org%3Arust-lang+is%3Amerged+\
+         merged%3A2026-10-13T00%3A00%3A00Z..2026-10-19T23%3A59%3A59Z\n\n\
+         #### Compiler\n\n\
+         * [stabilize `let_chains` in the 2024 edition](https://github.com/rust-lang/rust/pull/140001)\n"
+    ), "{draft}");
+    assert!(draft.contains("This week's crate is [](), a"), "{draft}");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn push() {
+    let (dir, draft) = with_draft("push", "600");
+    let cache = dir.join("cache");
+    // an earlier issue dropped one of this week's PRs
+    fs::write(
+        cache.join("history.json"),
+        r#"{"version": 1, "issues": [{"number": 599, "included": [], "dropped": [
+            {"url": "https://github.com/rust-lang/rust/pull/140002", "reason": "rule 1"}]}]}"#,
+    )
+    .unwrap();
+    twirer(&dir, &["start"]);
+    // the editor takes cargo's PR out
+    let text = fs::read_to_string(&draft).unwrap();
+    let kept = text
+        .lines()
+        .filter(|l| !l.contains("cargo/pull/15001"))
+        .collect::<Vec<_>>();
+    fs::write(&draft, kept.join("\n")).unwrap();
+
+    let out = twirer(&dir, &["push"]);
+    assert!(out.contains("Not running git push"), "{out}");
+    let history = fs::read_to_string(cache.join("history.json")).unwrap();
+    let issue = &history[history.find("\"number\": 600").unwrap()..];
+    let dropped = |url: &str, reason: &str| {
+        format!(
+            "\"url\": \"https://github.com/rust-lang/{url}\",\n          \"reason\": \"{reason}\""
+        )
+    };
+    assert!(
+        issue.contains(&dropped("rust/pull/140002", "rule 1")),
+        "{issue}"
+    );
+    assert!(
+        issue.contains(&dropped("cargo/pull/15001", "removed by the editor")),
+        "{issue}"
+    );
+    assert!(!issue.contains(&dropped("rust/pull/140002", "removed by the editor")));
+    assert_eq!(
+        fs::read_to_string(cache.join("week_spec")).unwrap(),
+        "2026-10-20T00:00:00Z..2026-10-27T00:00:00Z"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn push_bad_number() {
+    let (dir, _) = with_draft("push-number", "six hundred");
+    let output = Command::new(env!("CARGO_BIN_EXE_twirer"))
+        .args(["--fixtures", "tests/fixtures/prs", "push"])
+        .env("TWIRER_WORKSPACE", &dir)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is not an issue number"));
+    // nothing happened
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("git"));
+    assert_eq!(
+        fs::read_to_string(dir.join("cache/week_spec")).unwrap(),
+        "2026-10-13..2026-10-20"
+    );
+    assert!(!dir.join("cache/history.json").exists());
+    fs::remove_dir_all(dir).unwrap();
+}